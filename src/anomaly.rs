@@ -0,0 +1,276 @@
+/// Drop-offs, spikes and plateaus in the Sonar Sweep depth series
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::sonar::str_to_num_vec;
+use crate::window::{Mean, Num, StdDev, Windowed};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A sudden change of depth that lasts. Its magnitude is the change of
+    /// depth over the range, positive when the floor drops away and negative
+    /// when it rises.
+    DropOff,
+    /// A single reading out of line with both of its neighbours. Its magnitude
+    /// is how far it is from their mean.
+    Spike,
+    /// A run of readings at the same depth. Its magnitude is that depth.
+    Plateau,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Kind::DropOff => "drop-off",
+            Kind::Spike => "spike",
+            Kind::Plateau => "plateau",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anomaly {
+    pub kind: Kind,
+    /// Index of the first reading of the range.
+    pub start: usize,
+    /// Index of the last reading of the range, inclusive.
+    pub end: usize,
+    pub magnitude: f64,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}..={} {:.2}",
+            self.kind, self.start, self.end, self.magnitude
+        )
+    }
+}
+
+/// Flags the changes of depth between consecutive readings that are either
+/// larger than an absolute threshold, or further than a number of standard
+/// deviations from the changes over the previous `window` readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detector {
+    window: usize,
+    z_score: Option<f64>,
+    delta: Option<f64>,
+    plateau: usize,
+    tolerance: f64,
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self {
+            window: 10,
+            z_score: Some(3.0),
+            delta: None,
+            plateau: 5,
+            tolerance: 0.0,
+        }
+    }
+}
+
+impl Detector {
+    /// Number of changes of depth the z-score of a change is computed against.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(2);
+        self
+    }
+
+    /// `None` disables the z-score threshold.
+    pub fn with_z_score(mut self, z_score: Option<f64>) -> Self {
+        self.z_score = z_score;
+        self
+    }
+
+    /// `None` disables the absolute threshold.
+    pub fn with_delta(mut self, delta: Option<f64>) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// Minimum number of readings of a plateau, and the largest change of
+    /// depth between two of its consecutive readings.
+    pub fn with_plateau(mut self, len: usize, tolerance: f64) -> Self {
+        self.plateau = len.max(2);
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Anomalies of `depths`, ordered by their start.
+    pub fn detect<T: Num>(&self, depths: &[T]) -> Vec<Anomaly> {
+        let deltas: Vec<f64> = depths
+            .windows(2)
+            .map(|w| w[1].to_f64() - w[0].to_f64())
+            .collect();
+        let flagged = self.flag(&deltas);
+
+        let mut res = vec![];
+        let mut i = 0;
+        while i < deltas.len() {
+            if !flagged[i] {
+                i += 1;
+                continue;
+            }
+
+            if self.is_spike(&deltas, &flagged, i) {
+                let around = (depths[i].to_f64() + depths[i + 2].to_f64()) / 2.0;
+                res.push(Anomaly {
+                    kind: Kind::Spike,
+                    start: i + 1,
+                    end: i + 1,
+                    magnitude: depths[i + 1].to_f64() - around,
+                });
+                i += 2;
+                continue;
+            }
+
+            let start = i;
+            while i + 1 < deltas.len() && flagged[i + 1] && deltas[i] * deltas[i + 1] > 0.0 {
+                // unless the run ends with one.
+                if self.is_spike(&deltas, &flagged, i + 1) {
+                    break;
+                }
+                i += 1;
+            }
+            res.push(Anomaly {
+                kind: Kind::DropOff,
+                start,
+                end: i + 1,
+                magnitude: depths[i + 1].to_f64() - depths[start].to_f64(),
+            });
+            i += 1;
+        }
+
+        res.extend(self.plateaus(depths, &deltas));
+        res.sort_by_key(|a| (a.start, a.end));
+        res
+    }
+
+    /// Whether the flagged change `i` is straight followed by a change back of
+    /// at least half of it, making the reading between them a spike. The
+    /// change back isn't necessarily flagged itself, as the spike skews the
+    /// baseline it is compared to.
+    fn is_spike(&self, deltas: &[f64], flagged: &[bool], i: usize) -> bool {
+        flagged[i]
+            && deltas
+                .get(i + 1)
+                .is_some_and(|&back| deltas[i] * back < 0.0 && back.abs() * 2.0 >= deltas[i].abs())
+    }
+
+    /// Whether each of `deltas` passes one of the thresholds.
+    fn flag(&self, deltas: &[f64]) -> Vec<bool> {
+        let means = Windowed::new(self.window, Mean).apply(deltas);
+        let std_devs = Windowed::new(self.window, StdDev).apply(deltas);
+
+        deltas
+            .iter()
+            .enumerate()
+            .map(|(i, &d)| {
+                let absolute = matches!(self.delta, Some(t) if d.abs() >= t);
+                // the baseline of a change is the window right before it.
+                let relative = match (self.z_score, i.checked_sub(self.window)) {
+                    (Some(z), Some(j)) if std_devs[j] > 0.0 => {
+                        ((d - means[j]) / std_devs[j]).abs() >= z
+                    }
+                    _ => false,
+                };
+                absolute || relative
+            })
+            .collect()
+    }
+
+    fn plateaus<T: Num>(&self, depths: &[T], deltas: &[f64]) -> Vec<Anomaly> {
+        let mut res = vec![];
+        let mut start = 0;
+        for i in 0..=deltas.len() {
+            if i < deltas.len() && deltas[i].abs() <= self.tolerance {
+                continue;
+            }
+            // readings `start..=i` are flat.
+            if i + 1 - start >= self.plateau {
+                res.push(Anomaly {
+                    kind: Kind::Plateau,
+                    start,
+                    end: i,
+                    magnitude: Windowed::new(i + 1 - start, Mean).apply(&depths[start..=i])[0],
+                });
+            }
+            start = i + 1;
+        }
+        res
+    }
+}
+
+/// Parses one depth per line like day 1 and looks for anomalies in them.
+pub fn detect_str(s: &str, detector: &Detector) -> Result<Vec<Anomaly>> {
+    Ok(detector.detect(&str_to_num_vec::<i64>(s)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let depths = [
+            100, 101, 103, 102, 104, 105, 104, 106, 107, 108, 150, 152, 151, 153, 154, 90, 155,
+            156, 156, 156, 156, 156, 157,
+        ];
+        let detector = Detector::default().with_window(5).with_plateau(4, 0.0);
+        let anomalies = detector.detect(&depths);
+        assert_eq!(
+            anomalies,
+            vec![
+                Anomaly {
+                    kind: Kind::DropOff,
+                    start: 9,
+                    end: 10,
+                    magnitude: 42.0
+                },
+                Anomaly {
+                    kind: Kind::Spike,
+                    start: 15,
+                    end: 15,
+                    magnitude: -64.5
+                },
+                Anomaly {
+                    kind: Kind::Plateau,
+                    start: 17,
+                    end: 21,
+                    magnitude: 156.0
+                },
+            ]
+        );
+        assert_eq!(anomalies[1].to_string(), "spike 15..=15 -64.50");
+    }
+
+    #[test]
+    fn test_delta_threshold() {
+        let detector = Detector::default()
+            .with_z_score(None)
+            .with_delta(Some(10.0));
+        let anomalies = detect_str("10\n30\n50\n52\n40\n", &detector).unwrap();
+        assert_eq!(
+            anomalies,
+            vec![
+                Anomaly {
+                    kind: Kind::DropOff,
+                    start: 0,
+                    end: 2,
+                    magnitude: 40.0
+                },
+                Anomaly {
+                    kind: Kind::DropOff,
+                    start: 3,
+                    end: 4,
+                    magnitude: -12.0
+                },
+            ]
+        );
+        assert!(detect_str("10\nx\n", &detector).is_err());
+        assert!(detector.detect::<i32>(&[]).is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+/// Planning of `forward`, `up` and `down` routes to a target
+///
+/// Under the aim mechanics of `NavMode::Aim`, `forward x` at aim `a` moves `x`
+/// ahead and `a * x` deeper. Reaching a depth change `dd` over a distance `dh`
+/// takes one `forward` if `dd` is a multiple of `dh`, and otherwise two, with
+/// aims on either side of `dd / dh`. An optimal route never needs more than
+/// two `forward`s, and the shortest one never takes more than four commands.
+use crate::error::{Error, Result};
+use crate::submarine::{Coord, Direction, Vector};
+
+/// State to reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub horizontal: i32,
+    pub depth: i32,
+    /// Aim to end with, any aim if `None`.
+    pub aim: Option<i32>,
+}
+
+/// What makes a route better than another.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Fewest commands, then smallest total magnitude.
+    #[default]
+    Length,
+    /// Smallest sum of the lengths of the commands, then fewest commands.
+    Magnitude,
+}
+
+/// Stretch of `length` covered with a constant `aim`.
+type Segment = (i64, i64);
+
+/// Plans a route from `start` with the given `aim` to `target`, or fails
+/// with `Error::Unsolvable` explaining why none exists.
+pub fn plan(start: Coord, aim: i32, target: Target, objective: Objective) -> Result<Vec<Vector>> {
+    let dh = i64::from(target.horizontal) - i64::from(start.0);
+    let dd = i64::from(target.depth) - i64::from(start.1);
+    let aim = i64::from(aim);
+
+    if dh < 0 {
+        return Err(Error::Unsolvable(format!(
+            "the target is {} behind and the submarine only moves forward",
+            -dh
+        )));
+    }
+    if dh == 0 && dd != 0 {
+        return Err(Error::Unsolvable(
+            "the depth only changes when moving forward, and the target is straight above or below"
+                .to_string(),
+        ));
+    }
+
+    let last = target.aim.map(i64::from);
+    let mut candidates: Vec<Vec<Segment>> = vec![];
+    if dh == 0 {
+        candidates.push(vec![]);
+    } else if dd % dh == 0 {
+        candidates.push(vec![(dd / dh, dh)]);
+    }
+    if dh >= 2 {
+        // the aims right below and above the mean slope, which steer the
+        // least overall.
+        let lo = dd.div_euclid(dh);
+        let x_hi = dd - lo * dh;
+        if x_hi != 0 {
+            candidates.push(vec![(lo, dh - x_hi), (lo + 1, x_hi)]);
+            candidates.push(vec![(lo + 1, x_hi), (lo, dh - x_hi)]);
+        }
+        // keep the current aim, then fix the depth with the smallest change
+        // of aim, over the longest distance.
+        let rest = dd - aim * dh;
+        if let Some(x2) = largest_divisor(rest, dh - 1) {
+            candidates.push(vec![(aim, dh - x2), (aim + rest / x2, x2)]);
+        }
+        if let Some(last) = last {
+            // keep the current aim, then switch straight to the target's.
+            if last != aim {
+                let x1 = (dd - last * dh) / (aim - last);
+                if (dd - last * dh) % (aim - last) == 0 && (1..dh).contains(&x1) {
+                    candidates.push(vec![(aim, x1), (last, dh - x1)]);
+                }
+            }
+            // end on the target's aim, after the closest aim that fixes the
+            // depth.
+            let rest = dd - last * dh;
+            if let Some(x1) = largest_divisor(rest, dh - 1) {
+                candidates.push(vec![(last + rest / x1, x1), (last, dh - x1)]);
+            }
+        }
+    }
+
+    let mut best: Option<(Vec<Vector>, (i64, i64))> = None;
+    let mut overflow = None;
+    for segments in candidates {
+        let route = match build(&segments, aim, target.aim) {
+            Ok(route) => route,
+            Err(e @ Error::Overflow(_)) => {
+                overflow = Some(e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let len = route.len() as i64;
+        let magnitude = route.iter().map(|v| i64::from(v.length())).sum();
+        let key = match objective {
+            Objective::Length => (len, magnitude),
+            Objective::Magnitude => (magnitude, len),
+        };
+        if best.as_ref().is_none_or(|(_, k)| key < *k) {
+            best = Some((route, key));
+        }
+    }
+    match (best, overflow) {
+        (Some((route, _)), _) => Ok(route),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(vec![]),
+    }
+}
+
+/// Largest divisor of `n` no greater than `max`, `None` if `n` is 0 or `max`
+/// is below 1.
+fn largest_divisor(n: i64, max: i64) -> Option<i64> {
+    let n = n.unsigned_abs();
+    let max = u64::try_from(max).ok().filter(|&m| m >= 1)?;
+    if n == 0 {
+        return None;
+    }
+    if n <= max {
+        return Some(n as i64);
+    }
+    // divisors above the square root pair with cofactors below it, the
+    // smallest cofactor giving the largest divisor.
+    let mut c = n.div_ceil(max);
+    while c.saturating_mul(c) <= n {
+        if n.is_multiple_of(c) {
+            return Some((n / c) as i64);
+        }
+        c += 1;
+    }
+    (1..=max.min(c))
+        .rev()
+        .find(|d| n.is_multiple_of(*d))
+        .map(|d| d as i64)
+}
+
+/// Commands following `segments` from `aim`, then turning to `last` if set.
+fn build(segments: &[Segment], mut aim: i64, last: Option<i32>) -> Result<Vec<Vector>> {
+    let mut route = vec![];
+    for &(to, length) in segments {
+        steer(&mut route, aim, to)?;
+        aim = to;
+        route.push(Vector::new(Direction::Forward, narrow(length)?));
+    }
+    if let Some(last) = last {
+        steer(&mut route, aim, i64::from(last))?;
+    }
+    Ok(route)
+}
+
+fn steer(route: &mut Vec<Vector>, from: i64, to: i64) -> Result<()> {
+    match to - from {
+        0 => (),
+        d if d > 0 => route.push(Vector::new(Direction::Down, narrow(d)?)),
+        d => route.push(Vector::new(Direction::Up, narrow(-d)?)),
+    }
+    Ok(())
+}
+
+fn narrow(n: i64) -> Result<i32> {
+    i32::try_from(n)
+        .map_err(|_| Error::Overflow(format!("command length {} doesn't fit in i32", n)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::submarine::Nav;
+
+    fn check(start: (i32, i32, i32), target: Target, objective: Objective) -> Vec<Vector> {
+        let route = plan(Coord(start.0, start.1), start.2, target, objective).unwrap();
+        let end = Nav::new(route.clone(), Coord(start.0, start.1))
+            .with_aim(start.2)
+            .follow();
+        assert_eq!(
+            (end.horizontal(), end.depth()),
+            (target.horizontal, target.depth)
+        );
+        if let Some(aim) = target.aim {
+            assert_eq!(end.aim(), aim);
+        }
+        assert!(route.iter().all(|v| v.length() > 0));
+        route
+    }
+
+    #[test]
+    fn test_plan() {
+        // the day 2 example.
+        let target = Target {
+            horizontal: 15,
+            depth: 60,
+            aim: Some(10),
+        };
+        let route = check((0, 0, 0), target, Objective::Length);
+        assert_eq!(
+            route,
+            vec![
+                Vector::new(Direction::Down, 4),
+                Vector::new(Direction::Forward, 15),
+                Vector::new(Direction::Down, 6),
+            ]
+        );
+
+        // back to the surface without diving on the way.
+        let target = Target {
+            horizontal: 20,
+            depth: 0,
+            aim: Some(0),
+        };
+        assert_eq!(
+            check((15, 60, 10), target, Objective::Length),
+            vec![
+                Vector::new(Direction::Up, 22),
+                Vector::new(Direction::Forward, 5),
+                Vector::new(Direction::Down, 12),
+            ]
+        );
+        assert_eq!(check((15, 60, 10), target, Objective::Magnitude).len(), 3);
+
+        let target = Target {
+            horizontal: 3,
+            depth: 0,
+            aim: None,
+        };
+        assert!(check((3, 0, 7), target, Objective::Length).is_empty());
+    }
+
+    #[test]
+    fn test_tie_breaks() {
+        // keeping the aim of 10 for 1 rather than 9 steers much less.
+        let target = Target {
+            horizontal: 10,
+            depth: 55,
+            aim: None,
+        };
+        let expected = vec![
+            Vector::new(Direction::Forward, 1),
+            Vector::new(Direction::Up, 5),
+            Vector::new(Direction::Forward, 9),
+        ];
+        assert_eq!(check((0, 0, 10), target, Objective::Length), expected);
+        assert_eq!(check((0, 0, 10), target, Objective::Magnitude), expected);
+    }
+
+    /// Widest aim tried by `brute_force`, beyond any aim of an optimal route
+    /// for the targets of `test_plan_exhaustive`.
+    const MAX_AIM: i64 = 18;
+
+    /// Pushes the number of commands and the magnitude of every route of up
+    /// to `forwards` commands `forward` with aims within `MAX_AIM`.
+    fn brute_force(
+        (dh, dd, aim): (i64, i64, i64),
+        last: Option<i64>,
+        forwards: usize,
+        (len, magnitude): (i64, i64),
+        out: &mut Vec<(i64, i64)>,
+    ) {
+        if dh == 0 {
+            if dd == 0 {
+                let steer = last.map_or(0, |l| (l - aim).abs());
+                out.push((len + i64::from(steer != 0), magnitude + steer));
+            }
+            return;
+        }
+        if forwards == 0 {
+            return;
+        }
+        for to in -MAX_AIM..=MAX_AIM {
+            let steer = (to - aim).abs();
+            for x in 1..=dh {
+                // the last `forward` must reach the target.
+                if forwards == 1 && (x != dh || to * x != dd) {
+                    continue;
+                }
+                brute_force(
+                    (dh - x, dd - to * x, to),
+                    last,
+                    forwards - 1,
+                    (len + i64::from(steer != 0) + 1, magnitude + steer + x),
+                    out,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_exhaustive() {
+        for dh in 0..5 {
+            for dd in -6..=6 {
+                for aim in -2..=2 {
+                    for last in [None, Some(-2), Some(0), Some(2)] {
+                        let target = Target {
+                            horizontal: 10 + dh,
+                            depth: 5 + dd,
+                            aim: last,
+                        };
+                        if dh == 0 && dd != 0 {
+                            assert!(plan(Coord(10, 5), aim, target, Objective::Length).is_err());
+                            continue;
+                        }
+
+                        let mut routes = vec![];
+                        let last = last.map(i64::from);
+                        let start = (i64::from(dh), i64::from(dd), i64::from(aim));
+                        brute_force(start, last, 3, (0, 0), &mut routes);
+                        let key = |route: &[Vector]| {
+                            let len = route.len() as i64;
+                            (len, route.iter().map(|v| i64::from(v.length())).sum())
+                        };
+
+                        let short = check((10, 5, aim), target, Objective::Length);
+                        assert_eq!(
+                            Some(key(&short)),
+                            routes.iter().copied().min(),
+                            "{:?}",
+                            target
+                        );
+                        let light = check((10, 5, aim), target, Objective::Magnitude);
+                        let (len, magnitude) = key(&light);
+                        assert_eq!(
+                            Some((magnitude, len)),
+                            routes.iter().map(|&(l, m)| (m, l)).min(),
+                            "{:?}",
+                            target
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let target = Target {
+            horizontal: 1,
+            depth: 0,
+            aim: None,
+        };
+        match plan(Coord(2, 0), 0, target, Objective::Length).unwrap_err() {
+            Error::Unsolvable(reason) => assert!(reason.contains("1 behind")),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
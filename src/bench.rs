@@ -0,0 +1,186 @@
+/// Benchmarks of the parse and solve stages of each day
+use std::{
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::error::Result;
+use crate::solution::{Part, Puzzle};
+
+/// Summary of the timings of one stage over many iterations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        samples.sort();
+        let mid = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[mid - 1] + samples[mid]) / 2
+        } else {
+            samples[mid]
+        };
+
+        Self {
+            min: samples[0],
+            median,
+            max: samples[samples.len() - 1],
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "{{\"min_ns\": {}, \"median_ns\": {}, \"max_ns\": {}}}",
+            self.min.as_nanos(),
+            self.median.as_nanos(),
+            self.max.as_nanos()
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayReport {
+    pub day: u8,
+    /// Number of times each stage was run.
+    pub iterations: usize,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+/// Times each stage of `puzzle` separately, `iterations` times over, and at
+/// least once.
+pub fn bench(puzzle: &dyn Puzzle, input: &str, iterations: usize) -> Result<DayReport> {
+    let iterations = iterations.max(1);
+
+    let mut samples = Vec::with_capacity(iterations);
+    let mut parsed = None;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let p = puzzle.parse(black_box(input))?;
+        samples.push(start.elapsed());
+        // drop the previous parsed input outside of the timed section.
+        parsed = Some(p);
+    }
+    let parse = Stats::from_samples(samples);
+    let parsed = parsed.expect("at least one iteration was run");
+
+    let mut stages = [Stats::default(); 2];
+    for (stats, part) in stages.iter_mut().zip([Part::One, Part::Two]) {
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let start = Instant::now();
+            black_box(puzzle.solve(parsed.as_ref(), part)?);
+            samples.push(start.elapsed());
+        }
+        *stats = Stats::from_samples(samples);
+    }
+
+    Ok(DayReport {
+        day: puzzle.day(),
+        iterations,
+        parse,
+        part1: stages[0],
+        part2: stages[1],
+    })
+}
+
+/// Renders the reports as JSON, with every duration in nanoseconds.
+pub fn to_json(reports: &[DayReport]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{{");
+    let _ = writeln!(out, "  \"days\": [");
+    for (i, r) in reports.iter().enumerate() {
+        let _ = write!(
+            out,
+            "    {{\"day\": {}, \"iterations\": {}, \"parse\": ",
+            r.day, r.iterations
+        );
+        r.parse.write_json(&mut out);
+        out.push_str(", \"part1\": ");
+        r.part1.write_json(&mut out);
+        out.push_str(", \"part2\": ");
+        r.part2.write_json(&mut out);
+        out.push('}');
+        if i + 1 < reports.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    let _ = writeln!(out, "  ]");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::find;
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::from_samples(vec![ms(5), ms(1), ms(3)]);
+        assert_eq!(stats.min, ms(1));
+        assert_eq!(stats.median, ms(3));
+        assert_eq!(stats.max, ms(5));
+
+        let stats = Stats::from_samples(vec![ms(4), ms(1), ms(2), ms(8)]);
+        assert_eq!(stats.median, ms(3));
+
+        assert_eq!(Stats::from_samples(vec![]), Stats::default());
+    }
+
+    #[test]
+    fn test_bench() {
+        let report = bench(find(1).unwrap(), "1\n2\n3\n4", 3).unwrap();
+        assert_eq!(report.day, 1);
+        assert!(report.parse.min <= report.parse.median);
+        assert!(report.part2.median <= report.part2.max);
+        assert_eq!(report.iterations, 3);
+        assert_eq!(bench(find(1).unwrap(), "1", 0).unwrap().iterations, 1);
+
+        assert!(bench(find(1).unwrap(), "1\nx", 3).is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let ns = Duration::from_nanos;
+        let stats = Stats {
+            min: ns(1),
+            median: ns(2),
+            max: ns(3),
+        };
+        let report = DayReport {
+            day: 1,
+            iterations: 10,
+            parse: stats,
+            part1: stats,
+            part2: stats,
+        };
+        let stage = "{\"min_ns\": 1, \"median_ns\": 2, \"max_ns\": 3}";
+        assert_eq!(
+            to_json(&[report, report]),
+            format!(
+                "{{
+  \"days\": [
+    {{\"day\": 1, \"iterations\": 10, \"parse\": {0}, \"part1\": {0}, \"part2\": {0}}},
+    {{\"day\": 1, \"iterations\": 10, \"parse\": {0}, \"part1\": {0}, \"part2\": {0}}}
+  ]
+}}
+",
+                stage
+            )
+        );
+    }
+}
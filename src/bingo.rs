@@ -1,18 +1,16 @@
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-#[allow(dead_code)]
+use crate::error::{Error, ParseError, Result};
+use crate::solution::{Answer, Solution};
+use crate::trace::{event, Level};
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CellState {
     Checked,
+    #[default]
     Unchecked,
 }
 
-impl Default for CellState {
-    fn default() -> Self {
-        Self::Unchecked
-    }
-}
-
 impl Display for CellState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -38,14 +36,26 @@ impl From<i32> for Cell {
 }
 
 impl FromStr for Cell {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let n = i32::from_str_radix(s, 10)?;
+        let n = s
+            .parse::<i32>()
+            .map_err(|_| ParseError::new(s, s, "invalid number"))?;
         Ok(Cell::from(n))
     }
 }
 
+impl Cell {
+    pub fn value(&self) -> i32 {
+        self.val
+    }
+
+    pub fn state(&self) -> CellState {
+        self.state
+    }
+}
+
 impl Display for Cell {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} {}", self.val, self.state)
@@ -65,9 +75,12 @@ impl<const N: usize> Default for Row<N> {
     }
 }
 
-#[allow(dead_code)]
 impl<const N: usize> Row<N> {
-    fn complete(self) -> bool {
+    pub fn cells(&self) -> &[Cell; N] {
+        &self.inner
+    }
+
+    pub fn complete(self) -> bool {
         self.inner
             .iter()
             .all(|cell| cell.state == CellState::Checked)
@@ -105,11 +118,19 @@ impl<const N: usize> Display for Board<N> {
     }
 }
 
-#[allow(dead_code)]
 impl<const N: usize> Board<N> {
-    fn draw(&mut self, n: i32) {
+    pub fn rows(&self) -> &[Row<N>; N] {
+        &self.rows
+    }
+
+    pub fn cols(&self) -> &[Col<N>; N] {
+        &self.cols
+    }
+
+    /// Checks every cell holding the number `n`.
+    pub fn draw(&mut self, n: i32) {
         for r in &mut self.rows {
-            for mut cell in &mut r.inner {
+            for cell in &mut r.inner {
                 if cell.val == n {
                     cell.state = CellState::Checked;
                 }
@@ -117,7 +138,7 @@ impl<const N: usize> Board<N> {
         }
 
         for c in &mut self.cols {
-            for mut cell in &mut c.inner {
+            for cell in &mut c.inner {
                 if cell.val == n {
                     cell.state = CellState::Checked;
                 }
@@ -125,7 +146,8 @@ impl<const N: usize> Board<N> {
         }
     }
 
-    fn complete(self) -> bool {
+    /// Returns true if any row or column is fully checked.
+    pub fn complete(self) -> bool {
         for row in self.rows {
             if row.complete() {
                 return true;
@@ -151,45 +173,45 @@ impl<const N: usize> Board<N> {
     }
 }
 
-#[derive(Default, Debug)]
-#[allow(dead_code)]
+#[derive(Default, Clone, Debug)]
 pub struct Game<const N: usize> {
     boards: Vec<Board<N>>,
     draw: Vec<i32>,
 }
 
-#[allow(dead_code)]
 impl<const N: usize> Game<N> {
-    pub fn run(mut self) -> Option<(Board<N>, Vec<i32>)> {
-        let mut draw_iter = self.draw.iter();
-        for _ in 0..5 {
-            let curr = draw_iter
-                .next()
-                .expect("expected at least 5 numbers in draw");
-
-            for b in &mut self.boards {
-                b.draw(*curr);
-            }
-        }
+    pub fn boards(&self) -> &[Board<N>] {
+        &self.boards
+    }
 
-        for b in &self.boards {
-            if b.complete() {
-                return Some((*b, self.draw[0..5].into()));
-            }
-        }
+    /// Numbers to draw, in order.
+    pub fn draw(&self) -> &[i32] {
+        &self.draw
+    }
 
-        let mut i = 6;
-        for next in draw_iter {
+    pub fn run(mut self) -> Option<(Board<N>, Vec<i32>)> {
+        for (i, next) in self.draw.iter().enumerate() {
+            event!(Level::Trace, "drawing {}", next);
             for b in &mut self.boards {
                 b.draw(*next);
             }
+            // no row or column can be complete before N draws.
+            if i + 1 < N {
+                continue;
+            }
 
-            for b in &self.boards {
+            for (n, b) in self.boards.iter().enumerate() {
                 if b.complete() {
-                    return Some((*b, self.draw[0..i].into()));
+                    event!(
+                        Level::Debug,
+                        "board {} wins after {} draws\n{}",
+                        n,
+                        i + 1,
+                        b
+                    );
+                    return Some((*b, self.draw[0..=i].into()));
                 }
             }
-            i += 1;
         }
 
         None
@@ -198,11 +220,19 @@ impl<const N: usize> Game<N> {
     pub fn last_winner(mut self) -> Option<(Board<N>, Vec<i32>)> {
         let mut remaining = self.boards.len();
         for (i, next) in self.draw.clone().into_iter().enumerate() {
-            for b in &mut self.boards {
+            event!(Level::Trace, "drawing {}", next);
+            for (n, b) in self.boards.iter_mut().enumerate() {
                 if !b.complete() {
                     b.draw(next);
                     if b.complete() {
                         remaining -= 1;
+                        event!(
+                            Level::Debug,
+                            "board {} wins after {} draws, {} remaining",
+                            n,
+                            i + 1,
+                            remaining
+                        );
                     }
 
                     if remaining == 0 {
@@ -217,16 +247,20 @@ impl<const N: usize> Game<N> {
 }
 
 impl<const N: usize> FromStr for Game<N> {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let draw: Vec<i32> = lines
+        let mut lines = s.lines().enumerate();
+        let (_, first) = lines
             .next()
-            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?
+            .ok_or_else(|| ParseError::new(s, s, "expected the numbers to draw"))?;
+        let draw: Vec<i32> = first
             .split(',')
-            .map(|s| i32::from_str_radix(s, 10).unwrap())
-            .collect();
+            .map(|n| {
+                n.parse::<i32>()
+                    .map_err(|_| ParseError::new(first, n, "invalid number"))
+            })
+            .collect::<Result<_, _>>()?;
 
         // Consume the first empty line after the draw.
         let _ = lines.next();
@@ -234,36 +268,108 @@ impl<const N: usize> FromStr for Game<N> {
         let mut boards: Vec<Board<N>> = vec![];
         let mut curr_board: Board<N> = Default::default();
         let mut i = 0;
-        for line in lines {
-            let line = line.trim();
+        // last row of the current board, and its 0-based line number.
+        let mut last = (0, "");
+        for (n, raw) in lines {
+            let line = raw.trim();
             if !line.is_empty() {
+                if i >= N {
+                    let reason = format!("expected at most {} rows per board", N);
+                    return Err(ParseError::new(raw, line, reason).at_line(n + 1).into());
+                }
+                let mut count = 0;
                 for (j, num) in line.split_ascii_whitespace().enumerate() {
-                    let cell = Cell::from_str(num.trim())?;
+                    if j >= N {
+                        let reason = format!("expected at most {} numbers per row", N);
+                        return Err(ParseError::new(raw, num, reason).at_line(n + 1).into());
+                    }
+                    let cell = num
+                        .parse::<i32>()
+                        .map(Cell::from)
+                        .map_err(|_| ParseError::new(raw, num, "invalid number").at_line(n + 1))?;
                     curr_board.rows[i].inner[j] = cell;
                     curr_board.cols[j].inner[i] = cell;
+                    count += 1;
+                }
+                if count < N {
+                    let reason = format!("expected {} numbers per row", N);
+                    return Err(ParseError::new(raw, line, reason).at_line(n + 1).into());
                 }
                 i += 1;
+                last = (n, raw);
             } else {
                 // an empty line denotes the end of one board and the subsequent
                 // beginning of another.
-                boards.push(curr_board);
+                push_board(&mut boards, curr_board, i, last)?;
                 curr_board = Default::default();
                 i = 0;
             }
         }
 
         // push the last board
-        boards.push(curr_board);
+        push_board(&mut boards, curr_board, i, last)?;
 
         Ok(Self { draw, boards })
     }
 }
 
+/// Adds a board of `rows` rows ending on the `last` line, skipping it if it
+/// has no rows at all, e.g. between two empty lines.
+fn push_board<const N: usize>(
+    boards: &mut Vec<Board<N>>,
+    board: Board<N>,
+    rows: usize,
+    (n, raw): (usize, &str),
+) -> Result<()> {
+    match rows {
+        0 => Ok(()),
+        rows if rows < N => {
+            let reason = format!("expected {} rows per board", N);
+            Err(ParseError::new(raw, raw.trim(), reason)
+                .at_line(n + 1)
+                .into())
+        }
+        _ => {
+            boards.push(board);
+            Ok(())
+        }
+    }
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u8 = 4;
+    type Input = Game<5>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Game::from_str(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        score(input.clone().run())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        score(input.clone().last_winner())
+    }
+}
+
+/// Multiplies the unchecked cells of the winning board by the last number
+/// drawn.
+fn score<const N: usize>(winner: Option<(Board<N>, Vec<i32>)>) -> Result<Answer> {
+    let (board, draw) =
+        winner.ok_or_else(|| Error::Unsolvable("no board won the game".to_string()))?;
+    let last = draw.last().copied().unwrap_or_default();
+    Ok(i64::from(board.sum_unchecked()) * i64::from(last))
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
 
     use super::*;
+    use crate::error::parse_err;
 
     #[test]
     fn test_from_str() {
@@ -377,4 +483,57 @@ mod test {
         assert_eq!(last.cols[0].inner[0].val, 3);
         assert_eq!(*draw.last().unwrap(), 13);
     }
+
+    #[test]
+    fn test_from_str_errors() {
+        let raw = "7,4,x,5
+
+22 13 17 11  0";
+        let e = parse_err(Game::<5>::from_str(raw));
+        assert_eq!(e.line, 1);
+        assert_eq!(e.column, 5);
+        assert_eq!(e.token, "x");
+
+        let raw = "7,4,9,5
+
+22 13 17 11  0
+ 8  2 23  4 24 12";
+        let e = parse_err(Game::<5>::from_str(raw));
+        assert_eq!(e.line, 4);
+        assert_eq!(e.column, 16);
+        assert_eq!(e.token, "12");
+
+        // incomplete rows and boards would otherwise be padded with zeros.
+        let raw = "7,4,9,5
+
+22 13 17 11  0
+ 8  2 23";
+        let e = parse_err(Game::<5>::from_str(raw));
+        assert_eq!((e.line, e.column), (4, 2));
+        assert_eq!(e.reason, "expected 5 numbers per row");
+
+        let raw = "7,4,9,5
+
+22 13 17 11  0
+ 8  2 23  4 24
+
+ 3 15  0  2 22";
+        let e = parse_err(Game::<5>::from_str(raw));
+        assert_eq!((e.line, e.column), (4, 2));
+        assert_eq!(e.reason, "expected 5 rows per board");
+    }
+
+    #[test]
+    fn test_short_draw() {
+        let raw = "22,13
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19";
+        let game: Game<5> = Game::from_str(raw).unwrap();
+        assert!(game.clone().run().is_none());
+        assert!(matches!(Day4::part1(&game), Err(Error::Unsolvable(_))));
+    }
 }
@@ -0,0 +1,229 @@
+/// Comparison of two Sonar Sweep reports of the same area
+use crate::error::Result;
+use crate::sonar::str_to_num_vec;
+use crate::window::{Mean, Num, Windowed};
+
+/// Difference between two aligned readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    /// Index of the reading in the first sweep.
+    pub a: usize,
+    /// Index of the reading in the second sweep.
+    pub b: usize,
+    /// Second depth minus the first one.
+    pub delta: f64,
+}
+
+/// Run of aligned readings that disagree by more than the threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    /// Index of the first reading of the run in the first sweep.
+    pub start: usize,
+    /// Index of the last reading of the run in the first sweep, inclusive.
+    pub end: usize,
+    /// Delta of the largest magnitude in the run.
+    pub peak: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Reading `i` of the second sweep lines up with reading `i + offset` of
+    /// the first one.
+    pub offset: isize,
+    /// Pearson correlation of the aligned readings, `None` if it is undefined
+    /// for every offset, e.g. for flat sweeps.
+    pub correlation: Option<f64>,
+    pub deltas: Vec<Delta>,
+    pub regions: Vec<Region>,
+}
+
+/// Aligns two sweeps on the offset where they correlate best, then diffs
+/// them reading by reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparer {
+    max_offset: usize,
+    min_overlap: usize,
+    smoothing: usize,
+    threshold: f64,
+}
+
+impl Default for Comparer {
+    fn default() -> Self {
+        Self {
+            max_offset: 10,
+            min_overlap: 3,
+            smoothing: 1,
+            threshold: 10.0,
+        }
+    }
+}
+
+impl Comparer {
+    /// Largest shift tried in either direction.
+    pub fn with_max_offset(mut self, max_offset: usize) -> Self {
+        self.max_offset = max_offset;
+        self
+    }
+
+    /// Smallest number of aligned readings for an offset to be considered.
+    pub fn with_min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap.max(2);
+        self
+    }
+
+    /// Correlates the means of windows of `size` readings rather than the raw
+    /// readings, to find the offset of noisy sweeps. The deltas are always
+    /// between raw readings.
+    pub fn with_smoothing(mut self, size: usize) -> Self {
+        self.smoothing = size.max(1);
+        self
+    }
+
+    /// Largest delta, in absolute value, at which readings still agree.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn compare<T: Num>(&self, a: &[T], b: &[T]) -> Comparison {
+        let (offset, correlation) = self.best_offset(a, b);
+        let deltas: Vec<Delta> = aligned(a.len(), b.len(), offset)
+            .map(|(i, j)| Delta {
+                a: i,
+                b: j,
+                delta: b[j].to_f64() - a[i].to_f64(),
+            })
+            .collect();
+
+        let mut regions: Vec<Region> = vec![];
+        for d in deltas.iter().filter(|d| d.delta.abs() > self.threshold) {
+            match regions.last_mut() {
+                Some(r) if r.end + 1 == d.a => {
+                    r.end = d.a;
+                    if d.delta.abs() > r.peak.abs() {
+                        r.peak = d.delta;
+                    }
+                }
+                _ => regions.push(Region {
+                    start: d.a,
+                    end: d.a,
+                    peak: d.delta,
+                }),
+            }
+        }
+
+        Comparison {
+            offset,
+            correlation,
+            deltas,
+            regions,
+        }
+    }
+
+    /// Offset with the highest correlation, the smallest one on ties.
+    fn best_offset<T: Num>(&self, a: &[T], b: &[T]) -> (isize, Option<f64>) {
+        let smooth = |s: &[T]| Windowed::new(self.smoothing, Mean).apply(s);
+        let (a, b) = (smooth(a), smooth(b));
+
+        let max = self.max_offset as isize;
+        let mut offsets: Vec<isize> = (-max..=max).collect();
+        offsets.sort_by_key(|o| o.abs());
+
+        let mut best = (0, None);
+        for offset in offsets {
+            let pairs: Vec<(f64, f64)> = aligned(a.len(), b.len(), offset)
+                .map(|(i, j)| (a[i], b[j]))
+                .collect();
+            if pairs.len() < self.min_overlap {
+                continue;
+            }
+            if let Some(r) = pearson(&pairs) {
+                if best.1.is_none_or(|best| r > best) {
+                    best = (offset, Some(r));
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Indices of the readings of sweeps of `a` and `b` readings that line up
+/// when shifted by `offset`.
+fn aligned(a: usize, b: usize, offset: isize) -> impl Iterator<Item = (usize, usize)> {
+    (0..b).filter_map(move |j| {
+        let i = j as isize + offset;
+        (i >= 0 && (i as usize) < a).then_some((i as usize, j))
+    })
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    let (ma, mb) = pairs
+        .iter()
+        .fold((0.0, 0.0), |(sa, sb), (x, y)| (sa + x / n, sb + y / n));
+    let (mut cov, mut va, mut vb) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - ma) * (y - mb);
+        va += (x - ma).powi(2);
+        vb += (y - mb).powi(2);
+    }
+    (va > 0.0 && vb > 0.0).then(|| cov / (va * vb).sqrt())
+}
+
+/// Parses two reports of one depth per line like day 1 and compares them.
+pub fn compare_str(a: &str, b: &str, comparer: &Comparer) -> Result<Comparison> {
+    let a = str_to_num_vec::<i64>(a)?;
+    let b = str_to_num_vec::<i64>(b)?;
+    Ok(comparer.compare(&a, &b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DATA: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    fn test_compare() {
+        // the second sweep started two readings later and saw a wreck.
+        let b = [208, 210, 200, 207, 280, 285, 260, 263];
+        let cmp = Comparer::default().compare(&DATA, &b);
+        assert_eq!(cmp.offset, 2);
+        assert!(cmp.correlation.unwrap() > 0.8);
+        assert_eq!(cmp.deltas.len(), 8);
+        assert_eq!(
+            cmp.deltas[4],
+            Delta {
+                a: 6,
+                b: 4,
+                delta: 40.0
+            }
+        );
+        assert_eq!(
+            cmp.regions,
+            vec![Region {
+                start: 6,
+                end: 7,
+                peak: 40.0
+            }]
+        );
+
+        let cmp = Comparer::default().compare(&b, &DATA);
+        assert_eq!(cmp.offset, -2);
+        assert_eq!(cmp.regions[0].start, 4);
+    }
+
+    #[test]
+    fn test_compare_str() {
+        let cmp = compare_str(
+            "1\n1\n1\n",
+            "1\n1\n5\n",
+            &Comparer::default().with_threshold(1.0),
+        )
+        .unwrap();
+        assert_eq!(cmp.offset, 0);
+        assert_eq!(cmp.correlation, None);
+        assert_eq!(cmp.regions.len(), 1);
+        assert!(compare_str("1\n", "x\n", &Comparer::default()).is_err());
+    }
+}
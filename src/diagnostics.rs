@@ -1,11 +1,11 @@
 use std::str::FromStr;
 
-use anyhow::{format_err, Result};
+use crate::error::{Error, ParseError, Result};
+use crate::solution::{Answer, Solution};
+use crate::trace::{event, Level};
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct Diag<const N: u32> {
-    orig: String,
     gamma: u32,
     epsilon: u32,
     o2_rating: u32,
@@ -13,24 +13,42 @@ pub struct Diag<const N: u32> {
 }
 
 impl<const N: u32> Diag<N> {
-    #[allow(dead_code)]
+    /// Most common bits of the report.
+    pub fn gamma(&self) -> u32 {
+        self.gamma
+    }
+
+    /// Least common bits of the report.
+    pub fn epsilon(&self) -> u32 {
+        self.epsilon
+    }
+
+    pub fn o2_rating(&self) -> u32 {
+        self.o2_rating
+    }
+
+    pub fn co2_rating(&self) -> u32 {
+        self.co2_rating
+    }
+
     pub fn power_consumption(&self) -> u32 {
         self.gamma * self.epsilon
     }
 
-    #[allow(dead_code)]
     pub fn life_support_rating(&self) -> u32 {
         self.o2_rating * self.co2_rating
     }
 }
 
 impl<const N: u32> FromStr for Diag<N> {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        validate(s, N as usize)?;
+
         let owned = String::from(s);
-        let cap_size = N.try_into()?;
-        let bit_count = count_bits(owned.lines().map(String::from).collect(), cap_size)?;
+        let cap_size = N as usize;
+        let bit_count = count_bits(owned.lines().map(String::from).collect(), cap_size);
 
         // aka: most common bits.
         let mut gamma: u32 = 0;
@@ -45,69 +63,74 @@ impl<const N: u32> FromStr for Diag<N> {
         let mut nums: Vec<String> = owned.lines().map(String::from).collect();
         let mut curr_count = bit_count.clone();
         let mut i = 0;
-        while nums.len() > 1 {
-            println!("{:?}", nums);
-            println!("{:?}", curr_count);
-            nums = nums
-                .into_iter()
-                .filter(|s| {
-                    let c = s.chars().nth(i).unwrap();
-                    if curr_count[i].0 == curr_count[i].1 {
-                        return c == '1';
-                    }
-                    match c {
-                        '0' => curr_count[i].0 > curr_count[i].1,
-                        '1' => curr_count[i].0 < curr_count[i].1,
-                        _ => false,
-                    }
-                })
-                .collect();
-            curr_count = count_bits(nums.clone(), cap_size)?;
+        // duplicate numbers are still left after the last bit.
+        while nums.len() > 1 && i < cap_size {
+            event!(Level::Trace, "o2 rating, bit {}: {:?}", i, nums);
+            event!(
+                Level::Trace,
+                "o2 rating, bit {}: counts {:?}",
+                i,
+                curr_count
+            );
+            nums.retain(|s| {
+                let c = s.chars().nth(i).unwrap();
+                if curr_count[i].0 == curr_count[i].1 {
+                    return c == '1';
+                }
+                match c {
+                    '0' => curr_count[i].0 > curr_count[i].1,
+                    '1' => curr_count[i].0 < curr_count[i].1,
+                    _ => false,
+                }
+            });
+            curr_count = count_bits(nums.clone(), cap_size);
             i += 1;
         }
 
         if nums.len() != 1 {
-            return Err(format_err!(
+            return Err(Error::Unsolvable(format!(
                 "expected exactly one number for o2 rating, found {}",
                 nums.len()
-            ));
+            )));
         }
 
-        let o2_rating = u32::from_str_radix(nums.get(0).unwrap(), 2)?;
+        let o2_rating = from_bits(nums.first().unwrap());
 
         // Get the Co2 Rating, similar to o2
         let mut nums: Vec<String> = owned.lines().map(String::from).collect();
         let mut curr_count = bit_count;
         let mut i = 0;
-        while nums.len() > 1 {
-            println!("{:?}", nums);
-            println!("{:?}", curr_count);
-            nums = nums
-                .into_iter()
-                .filter(|s| {
-                    let c = s.chars().nth(i).unwrap();
-                    if curr_count[i].0 == curr_count[i].1 {
-                        return c == '0';
-                    }
-                    match c {
-                        '0' => curr_count[i].0 < curr_count[i].1,
-                        '1' => curr_count[i].0 > curr_count[i].1,
-                        _ => false,
-                    }
-                })
-                .collect();
-            curr_count = count_bits(nums.clone(), cap_size)?;
+        while nums.len() > 1 && i < cap_size {
+            event!(Level::Trace, "co2 rating, bit {}: {:?}", i, nums);
+            event!(
+                Level::Trace,
+                "co2 rating, bit {}: counts {:?}",
+                i,
+                curr_count
+            );
+            nums.retain(|s| {
+                let c = s.chars().nth(i).unwrap();
+                if curr_count[i].0 == curr_count[i].1 {
+                    return c == '0';
+                }
+                match c {
+                    '0' => curr_count[i].0 < curr_count[i].1,
+                    '1' => curr_count[i].0 > curr_count[i].1,
+                    _ => false,
+                }
+            });
+            curr_count = count_bits(nums.clone(), cap_size);
             i += 1;
         }
 
         if nums.len() != 1 {
-            return Err(format_err!(
+            return Err(Error::Unsolvable(format!(
                 "expected exactly one number for Co2 rating, found {}",
                 nums.len()
-            ));
+            )));
         }
 
-        let co2_rating = u32::from_str_radix(nums.get(0).unwrap(), 2)?;
+        let co2_rating = from_bits(nums.first().unwrap());
 
         // The `!` bitwise operator flips each bit in the integer, the `&` operation
         // then makes sure that we're flipping all the extra unnecessary bits back
@@ -115,7 +138,6 @@ impl<const N: u32> FromStr for Diag<N> {
         // aka: least common bits.
         let epsilon = !gamma & (2_u32.pow(N) - 1);
         Ok(Self {
-            orig: owned,
             gamma,
             epsilon,
             o2_rating,
@@ -124,7 +146,30 @@ impl<const N: u32> FromStr for Diag<N> {
     }
 }
 
-fn count_bits(s: Vec<String>, bit_size: usize) -> Result<Vec<(u32, u32)>> {
+/// Checks that every line of the report is made of exactly `bit_size` binary
+/// digits.
+fn validate(s: &str, bit_size: usize) -> Result<()> {
+    for (i, line) in s.lines().enumerate() {
+        if let Some((j, c)) = line.char_indices().find(|(_, c)| *c != '0' && *c != '1') {
+            let token = &line[j..j + c.len_utf8()];
+            return Err(ParseError::new(line, token, "expected a binary digit")
+                .at_line(i + 1)
+                .into());
+        }
+        if line.len() != bit_size {
+            let reason = format!("expected {} bits, found {}", bit_size, line.len());
+            return Err(ParseError::new(line, line, reason).at_line(i + 1).into());
+        }
+    }
+    Ok(())
+}
+
+/// Reads a string of binary digits, already checked by `validate`.
+fn from_bits(s: &str) -> u32 {
+    s.chars().fold(0, |n, c| (n << 1) + u32::from(c == '1'))
+}
+
+fn count_bits(s: Vec<String>, bit_size: usize) -> Vec<(u32, u32)> {
     let mut bit_count: Vec<(u32, u32)> = Vec::with_capacity(bit_size);
     bit_count.resize(bit_size, (0, 0));
     for line in s {
@@ -132,16 +177,37 @@ fn count_bits(s: Vec<String>, bit_size: usize) -> Result<Vec<(u32, u32)>> {
             match c {
                 '0' => bit_count[i].0 += 1,
                 '1' => bit_count[i].1 += 1,
-                u => return Err(format_err!("unexpected char {}", u)),
+                _ => {}
             }
         }
     }
-    Ok(bit_count)
+    bit_count
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+    type Input = Diag<12>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Diag::from_str(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(input.power_consumption().into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(input.life_support_rating().into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::parse_err;
+    use crate::trace::{capture, Filter};
 
     #[test]
     fn test_from_str() {
@@ -157,12 +223,29 @@ mod tests {
 11001
 00010
 01010";
-        let diag = Diag::<5>::from_str(data).unwrap();
-        println!("{:?}", diag);
+        let (diag, events) = capture(Filter::new(Level::Trace), || Diag::<5>::from_str(data));
+        let diag = diag.unwrap();
+        assert_eq!(events[0].message, "o2 rating, bit 0: [\"00100\", \"11110\", \"10110\", \"10111\", \"10101\", \"01111\", \"00111\", \"11100\", \"10000\", \"11001\", \"00010\", \"01010\"]");
+        assert!(events.iter().all(|e| e.module == "aoc2021::diagnostics"));
         assert_eq!(diag.gamma, 22);
         assert_eq!(diag.epsilon, 9);
         assert_eq!(diag.o2_rating, 23);
         assert_eq!(diag.co2_rating, 10);
         assert_eq!(diag.power_consumption(), 198);
     }
+
+    #[test]
+    fn test_from_str_errors() {
+        let e = parse_err(Diag::<5>::from_str("00100\n11210"));
+        assert_eq!(e.line, 2);
+        assert_eq!(e.column, 3);
+        assert_eq!(e.token, "2");
+
+        assert_eq!(parse_err(Diag::<5>::from_str("00100\n1101")).line, 2);
+
+        match Diag::<12>::from_str("000000000001\n000000000001").unwrap_err() {
+            Error::Unsolvable(reason) => assert!(reason.contains("found 2")),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
 }
@@ -0,0 +1,196 @@
+/// Safety envelope of the submarine
+use crate::error::{Error, Result};
+use crate::submarine::{Direction, Vector};
+use crate::trace::{event, Level};
+
+/// What to do with a command breaking a constraint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Follow the command, then bring the state back within the limit, or
+    /// shorten the command for step limits.
+    #[default]
+    Clamp,
+    /// Skip the command entirely.
+    Reject,
+    /// Stop with `Error::Unsafe`. Takes precedence over the other policies
+    /// when a command breaks several constraints.
+    Abort,
+}
+
+/// Limits on the state of the submarine after each command, none by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    surface: Option<Policy>,
+    max_depth: Option<(i32, Policy)>,
+    max_aim: Option<(i32, Policy)>,
+    max_step: Option<(i32, Policy)>,
+}
+
+impl Envelope {
+    /// Keeps the submarine at depth 0 or below.
+    pub fn with_surface(mut self, policy: Policy) -> Self {
+        self.surface = Some(policy);
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: i32, policy: Policy) -> Self {
+        self.max_depth = Some((depth, policy));
+        self
+    }
+
+    /// Keeps the aim between `-aim` and `aim`.
+    pub fn with_max_aim(mut self, aim: i32, policy: Policy) -> Self {
+        self.max_aim = Some((aim.abs(), policy));
+        self
+    }
+
+    /// Limits the length of `forward`, `back`, `up`, `down` and `strafe`
+    /// commands, in either direction.
+    pub fn with_max_step(mut self, step: i32, policy: Policy) -> Self {
+        self.max_step = Some((step.abs(), policy));
+        self
+    }
+
+    /// Command to follow in place of the command at `index`, `None` if it
+    /// must be skipped.
+    pub(crate) fn check_command(&self, index: usize, v: &Vector) -> Result<Option<Vector>> {
+        let moves = matches!(
+            v.dir(),
+            Direction::Forward
+                | Direction::Back
+                | Direction::Up
+                | Direction::Down
+                | Direction::Strafe
+        );
+        match self.max_step {
+            Some((max, policy)) if moves && v.length().abs() > max => {
+                let reason = format!("step of {} is over the maximum of {}", v.length(), max);
+                event!(Level::Debug, "command {}: {}, {:?}", index, reason, policy);
+                match policy {
+                    Policy::Clamp => Ok(Some(Vector::new(v.dir(), v.length().clamp(-max, max)))),
+                    Policy::Reject => Ok(None),
+                    Policy::Abort => Err(Error::Unsafe(index, reason)),
+                }
+            }
+            _ => Ok(Some(v.clone())),
+        }
+    }
+
+    /// Brings the state after the command at `index` within the limits.
+    /// Returns `false` if the command must be undone instead.
+    pub(crate) fn check_state(&self, index: usize, depth: &mut i32, aim: &mut i32) -> Result<bool> {
+        let mut broken = vec![];
+        if let Some(policy) = self.surface.filter(|_| *depth < 0) {
+            broken.push((policy, format!("depth {} is above the surface", depth)));
+        }
+        if let Some((max, policy)) = self.max_depth.filter(|(max, _)| *depth > *max) {
+            broken.push((
+                policy,
+                format!("depth {} is past the maximum of {}", depth, max),
+            ));
+        }
+        if let Some((max, policy)) = self.max_aim.filter(|(max, _)| aim.abs() > *max) {
+            broken.push((
+                policy,
+                format!("aim {} is past the maximum of {}", aim, max),
+            ));
+        }
+        for (policy, reason) in &broken {
+            event!(Level::Debug, "command {}: {}, {:?}", index, reason, policy);
+        }
+
+        if let Some((_, reason)) = broken.iter().find(|(p, _)| *p == Policy::Abort) {
+            return Err(Error::Unsafe(index, reason.clone()));
+        }
+        if broken.iter().any(|(p, _)| *p == Policy::Reject) {
+            return Ok(false);
+        }
+        // every broken constraint is clamped at this point.
+        if self.surface.is_some() {
+            *depth = (*depth).max(0);
+        }
+        if let Some((max, _)) = self.max_depth {
+            *depth = (*depth).min(max);
+        }
+        if let Some((max, _)) = self.max_aim {
+            *aim = (*aim).clamp(-max, max);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::submarine::{Coord, Nav, NavMode};
+
+    fn nav(raw: &str, envelope: Envelope) -> Nav {
+        Nav::from(crate::error::parse_lines::<Vector>(raw).unwrap()).with_envelope(envelope)
+    }
+
+    #[test]
+    fn test_clamp() {
+        let envelope = Envelope::default()
+            .with_surface(Policy::Clamp)
+            .with_max_depth(20, Policy::Clamp)
+            .with_max_aim(3, Policy::Clamp)
+            .with_max_step(10, Policy::Clamp);
+        let end = nav("up 2\nforward 4\ndown 50\nforward 30", envelope).follow();
+        // aim -2 then 3, depth clamped at the surface then at 20.
+        assert_eq!((end.horizontal(), end.depth(), end.aim()), (14, 20, 3));
+
+        let end = Nav::new(crate::error::parse_lines("up 5").unwrap(), Coord(0, 2))
+            .with_mode(NavMode::Direct)
+            .with_envelope(envelope)
+            .follow();
+        assert_eq!(end.depth(), 0);
+    }
+
+    #[test]
+    fn test_reject() {
+        let envelope = Envelope::default()
+            .with_surface(Policy::Reject)
+            .with_max_step(10, Policy::Reject);
+        let nav = nav(
+            "forward 5\nup 1\nforward 2\ndown 20\ndown 2\nforward 3",
+            envelope,
+        );
+        let steps: Vec<_> = nav
+            .steps()
+            .map(|s| (s.horizontal, s.depth, s.aim))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (5, 0, 0),
+                (5, 0, -1),
+                (5, 0, -1),
+                (5, 0, -1),
+                (5, 0, 1),
+                (8, 3, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_abort() {
+        let envelope = Envelope::default()
+            .with_max_depth(10, Policy::Abort)
+            .with_max_aim(5, Policy::Reject);
+        let nav = nav("down 2\nforward 4\ndown 9\nforward 2\nforward 1", envelope);
+        match nav.clone().try_follow().unwrap_err() {
+            Error::Unsafe(index, reason) => {
+                assert_eq!(index, 3);
+                assert_eq!(reason, "depth 12 is past the maximum of 10");
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+
+        // stops right before the offending command.
+        let end = nav.clone().follow();
+        assert_eq!((end.horizontal(), end.depth()), (4, 8));
+        let mut steps = nav.steps();
+        assert_eq!(steps.by_ref().count(), 3);
+        assert!(steps.try_next().unwrap().is_none());
+    }
+}
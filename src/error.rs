@@ -0,0 +1,210 @@
+/// Errors shared by every module of the crate.
+use std::{fmt::Display, str::FromStr};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The puzzle input is malformed.
+    Parse(ParseError),
+    Io(std::io::Error),
+    /// A request to the puzzle website failed.
+    Http(String),
+    /// An answer wasn't submitted because it would be rejected anyway.
+    Refused(String),
+    /// The puzzle input is well formed but has no answer.
+    Unsolvable(String),
+    /// Arithmetic on the puzzle input doesn't fit in the numeric type used.
+    Overflow(String),
+    /// The command at the given index breaks the safety envelope of the
+    /// submarine.
+    Unsafe(usize, String),
+    InvalidPart(u8),
+}
+
+impl Error {
+    /// Sets the 1-based line number of a parse error within the whole input.
+    /// Other errors are returned as is.
+    pub fn at_line(self, line: usize) -> Self {
+        match self {
+            Error::Parse(e) => Error::Parse(e.at_line(line)),
+            e => e,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Http(e) => write!(f, "{}", e),
+            Error::Refused(reason) => write!(f, "refusing to submit: {}", reason),
+            Error::Unsolvable(reason) => write!(f, "no solution: {}", reason),
+            Error::Overflow(what) => write!(f, "overflow: {}", what),
+            Error::Unsafe(index, reason) => write!(f, "unsafe command {}: {}", index, reason),
+            Error::InvalidPart(n) => write!(f, "unknown part {}, expected 1 or 2", n),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Location and content of a malformed token in the puzzle input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column of the first character of the token.
+    pub column: usize,
+    pub token: String,
+    pub reason: String,
+    /// Full text of the offending line.
+    pub source: String,
+}
+
+impl ParseError {
+    /// Creates an error pointing at `token`, which must be a slice of `line`.
+    /// If it isn't, the error points at the start of the line.
+    pub fn new(line: &str, token: &str, reason: impl Into<String>) -> Self {
+        let start = line.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|o| o + token.len() <= line.len())
+            .unwrap_or(0);
+
+        Self {
+            line: 1,
+            column: line[..offset].chars().count() + 1,
+            token: token.to_string(),
+            reason: reason.into(),
+            source: line.to_string(),
+        }
+    }
+
+    /// Sets the 1-based line number of the error within the whole input.
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+}
+
+impl Display for ParseError {
+    /// Renders the error followed by the offending line, with a caret under
+    /// the token:
+    ///
+    /// ```text
+    /// line 2, column 9: invalid length `4x`
+    ///   |
+    /// 2 | forward 4x
+    ///   |         ^^
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.token.is_empty() {
+            writeln!(
+                f,
+                "line {}, column {}: {}",
+                self.line, self.column, self.reason
+            )?;
+        } else {
+            writeln!(
+                f,
+                "line {}, column {}: {} `{}`",
+                self.line, self.column, self.reason, self.token
+            )?;
+        }
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.token.chars().count().max(1))
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses every line of `input`, tagging errors with their line number.
+pub fn parse_lines<T>(input: &str) -> Result<Vec<T>>
+where
+    T: FromStr<Err = Error>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| T::from_str(line).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+/// Parse error of a result expected to fail parsing, panicking on any other
+/// outcome.
+#[cfg(test)]
+pub(crate) fn parse_err<T: std::fmt::Debug>(r: Result<T>) -> ParseError {
+    match r.unwrap_err() {
+        Error::Parse(e) => e,
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = "forward 4x";
+        let err = ParseError::new(line, &line[8..], "invalid length");
+        assert_eq!(err.column, 9);
+        assert_eq!(err.token, "4x");
+
+        // a token that isn't a slice of the line points at its start.
+        let err = ParseError::new(line, "4x", "invalid length");
+        assert_eq!(err.column, 1);
+    }
+
+    #[test]
+    fn test_display() {
+        let line = "forward 4x";
+        let err = ParseError::new(line, &line[8..], "invalid length").at_line(12);
+        assert_eq!(
+            err.to_string(),
+            "line 12, column 9: invalid length `4x`
+   |
+12 | forward 4x
+   |         ^^"
+        );
+
+        let err = ParseError::new(line, &line[line.len()..], "expected a length");
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 11: expected a length
+  |
+1 | forward 4x
+  |           ^"
+        );
+    }
+}
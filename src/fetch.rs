@@ -0,0 +1,207 @@
+/// Download and local cache of the puzzle inputs
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{Error, Result};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const YEAR: u16 = 2021;
+
+/// Server, session and event year shared by downloads and submissions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    base_url: String,
+    session: Option<String>,
+    year: u16,
+}
+
+impl Default for Connection {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            session: None,
+            year: YEAR,
+        }
+    }
+}
+
+impl Connection {
+    /// Sets the server to talk to, e.g. a local stand-in for tests.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the session token sent as a cookie to authenticate requests.
+    pub fn with_session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    pub fn with_year(mut self, year: u16) -> Self {
+        self.year = year;
+        self
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Authenticated request to the `endpoint` of a day, failing without a
+    /// session since the puzzle website requires one to `action`.
+    pub(crate) fn request(
+        &self,
+        method: &str,
+        day: u8,
+        endpoint: &str,
+        action: &str,
+    ) -> Result<ureq::Request> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| Error::Http(format!("a session token is required to {}", action)))?;
+        let url = format!("{}/{}/day/{}/{}", self.base_url, self.year, day, endpoint);
+        Ok(ureq::request(method, &url)
+            .set("Cookie", &format!("session={}", session))
+            .set("User-Agent", USER_AGENT))
+    }
+}
+
+/// Fetches puzzle inputs over HTTP and caches them on disk, keyed by year and
+/// day. An input found in the cache is never downloaded again.
+#[derive(Debug, Clone)]
+pub struct InputManager {
+    connection: Connection,
+    cache_dir: PathBuf,
+}
+
+impl InputManager {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            connection: Connection::default(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    pub fn with_connection(mut self, connection: Connection) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    pub fn cache_path(&self, day: u8) -> PathBuf {
+        self.cache_dir
+            .join(self.connection.year().to_string())
+            .join(format!("day{}.txt", day))
+    }
+
+    /// Returns the input of the given day if it is already cached.
+    pub fn cached(&self, day: u8) -> Result<Option<String>> {
+        match fs::read_to_string(self.cache_path(day)) {
+            Ok(s) => Ok(Some(s)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the input of the given day, downloading and caching it first if
+    /// it isn't cached yet.
+    pub fn get(&self, day: u8) -> Result<String> {
+        if let Some(input) = self.cached(day)? {
+            return Ok(input);
+        }
+
+        let input = self.download(day)?;
+        write_atomic(&self.cache_path(day), &input)?;
+        Ok(input)
+    }
+
+    fn download(&self, day: u8) -> Result<String> {
+        let req = self
+            .connection
+            .request("GET", day, "input", "download inputs")?;
+        let url = req.url().to_string();
+        let resp = req.call().map_err(|e| http_error(&url, e))?;
+        Ok(resp.into_string()?)
+    }
+}
+
+pub(crate) const USER_AGENT: &str = concat!(
+    "github.com/wperron/aoc2021 ",
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION")
+);
+
+pub(crate) fn http_error(url: &str, e: ureq::Error) -> Error {
+    match e {
+        ureq::Error::Status(code, _) => Error::Http(format!("{} returned status {}", url, code)),
+        ureq::Error::Transport(t) => Error::Http(format!("{}: {}", url, t)),
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` before moving it in
+/// place, so that an interrupted write never leaves a partial file behind.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::MockServer;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc2021-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_get_caches_input() {
+        let server = MockServer::start(|_| (200, "1\n2\n3\n".to_string()));
+        let dir = temp_dir("fetch");
+        let inputs = InputManager::new(&dir).with_connection(
+            Connection::default()
+                .with_base_url(&server.url)
+                .with_session("abc"),
+        );
+
+        assert_eq!(inputs.cached(1).unwrap(), None);
+        assert_eq!(inputs.get(1).unwrap(), "1\n2\n3\n");
+        assert_eq!(inputs.get(1).unwrap(), "1\n2\n3\n");
+        assert_eq!(inputs.cache_path(1), dir.join("2021").join("day1.txt"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/2021/day/1/input");
+        assert_eq!(requests[0].header("Cookie"), Some("session=abc"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_get_errors() {
+        let server = MockServer::start(|_| (404, "not found".to_string()));
+        let dir = temp_dir("fetch-errors");
+
+        let connection = Connection::default().with_base_url(&server.url);
+        let inputs = InputManager::new(&dir).with_connection(connection.clone());
+        assert!(matches!(inputs.get(1), Err(Error::Http(_))));
+        assert!(server.requests().is_empty());
+
+        let inputs = inputs.with_connection(connection.with_session("abc"));
+        assert!(matches!(inputs.get(1), Err(Error::Http(_))));
+        assert_eq!(inputs.cached(1).unwrap(), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,248 @@
+/// Lock-step simulation of several submarines
+///
+/// Every submarine follows one command of its own `Nav` per step, except
+/// for `hold n` which lasts `n` steps, and keeps its last state once it runs
+/// out of commands or its envelope aborts. Step 0 is the start.
+use std::collections::HashMap;
+
+use crate::submarine::{Direction, EndState, Nav};
+use crate::trace::{event, Level};
+
+/// Run of consecutive steps where two submarines are within the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encounter {
+    /// Indices of the submarines in the fleet, `a < b`.
+    pub a: usize,
+    pub b: usize,
+    /// First step of the run.
+    pub start: usize,
+    /// Last step of the run, inclusive.
+    pub end: usize,
+    /// Smallest distance during the run.
+    pub closest: i64,
+    /// First step at the smallest distance.
+    pub at: usize,
+}
+
+impl Encounter {
+    /// Whether both submarines were at the same position and depth.
+    pub fn is_collision(&self) -> bool {
+        self.closest == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation {
+    /// Number of steps until every submarine ran out of commands.
+    pub steps: usize,
+    /// Final state of each submarine.
+    pub ends: Vec<EndState>,
+    pub encounters: Vec<Encounter>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Fleet {
+    navs: Vec<Nav>,
+    threshold: i64,
+}
+
+impl Fleet {
+    pub fn new(navs: Vec<Nav>) -> Self {
+        Self {
+            navs,
+            ..Default::default()
+        }
+    }
+
+    /// Largest distance, summed over the horizontal position, `y` and depth,
+    /// at which two submarines meet. 0 by default, for collisions only.
+    pub fn with_threshold(mut self, threshold: u32) -> Self {
+        self.threshold = i64::from(threshold);
+        self
+    }
+
+    pub fn simulate(&self) -> Simulation {
+        let mut steps: Vec<_> = self.navs.iter().map(Nav::steps).collect();
+        // index of the next command of each submarine, and steps left to hold.
+        let mut clocks = vec![(0, 0); self.navs.len()];
+        let len = self.navs.iter().map(duration).max().unwrap_or_default();
+
+        let mut encounters: Vec<Encounter> = vec![];
+        // index in `encounters` of the last one of each pair.
+        let mut last: HashMap<(usize, usize), usize> = HashMap::new();
+        for step in 0..=len {
+            if step > 0 {
+                for ((nav, s), (next, hold)) in self.navs.iter().zip(&mut steps).zip(&mut clocks) {
+                    if *hold > 0 {
+                        *hold -= 1;
+                        continue;
+                    }
+                    // `hold 0` takes no time at all.
+                    while let Some(v) = nav.directions().get(*next) {
+                        *next += 1;
+                        s.next();
+                        match v.dir() {
+                            Direction::Hold if v.length() <= 0 => continue,
+                            Direction::Hold => *hold = v.length() - 1,
+                            _ => (),
+                        }
+                        break;
+                    }
+                }
+            }
+            let states: Vec<EndState> = steps.iter().map(|s| s.end_state()).collect();
+            for a in 0..states.len() {
+                for b in a + 1..states.len() {
+                    let d = distance(&states[a], &states[b]);
+                    if d > self.threshold {
+                        continue;
+                    }
+                    event!(
+                        Level::Debug,
+                        "step {}: submarines {} and {} are {} apart",
+                        step,
+                        a,
+                        b,
+                        d
+                    );
+                    match last.get(&(a, b)).map(|&i| &mut encounters[i]) {
+                        Some(e) if e.end + 1 == step => {
+                            e.end = step;
+                            if d < e.closest {
+                                (e.closest, e.at) = (d, step);
+                            }
+                        }
+                        _ => {
+                            last.insert((a, b), encounters.len());
+                            encounters.push(Encounter {
+                                a,
+                                b,
+                                start: step,
+                                end: step,
+                                closest: d,
+                                at: step,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Simulation {
+            steps: len,
+            ends: steps.iter().map(|s| s.end_state()).collect(),
+            encounters,
+        }
+    }
+}
+
+/// Number of steps `nav` takes to follow all of its commands.
+fn duration(nav: &Nav) -> usize {
+    nav.directions()
+        .iter()
+        .map(|v| match v.dir() {
+            Direction::Hold => v.length().max(0) as usize,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn distance(a: &EndState, b: &EndState) -> i64 {
+    let d = |x: i32, y: i32| (i64::from(x) - i64::from(y)).abs();
+    d(a.horizontal(), b.horizontal()) + d(a.y(), b.y()) + d(a.depth(), b.depth())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_lines;
+    use crate::submarine::{Coord, NavMode};
+
+    fn fleet() -> Fleet {
+        let sub = |raw: &str, start: Coord| {
+            Nav::new(parse_lines(raw).unwrap(), start).with_mode(NavMode::Direct)
+        };
+        Fleet::new(vec![
+            sub("forward 2\nforward 2\nforward 2", Coord(0, 0)),
+            sub("back 1\nback 1\nback 1", Coord(6, 0)),
+            // holds its depth after the first step.
+            sub("up 1", Coord(4, 2)),
+        ])
+    }
+
+    #[test]
+    fn test_collision() {
+        let sim = fleet().simulate();
+        assert_eq!(sim.steps, 3);
+        assert_eq!(
+            sim.ends.iter().map(|e| e.horizontal()).collect::<Vec<_>>(),
+            vec![6, 3, 4]
+        );
+        assert_eq!(
+            sim.encounters,
+            vec![Encounter {
+                a: 0,
+                b: 1,
+                start: 2,
+                end: 2,
+                closest: 0,
+                at: 2
+            }]
+        );
+        assert!(sim.encounters[0].is_collision());
+    }
+
+    #[test]
+    fn test_threshold() {
+        let sim = fleet().with_threshold(2).simulate();
+        let runs: Vec<_> = sim
+            .encounters
+            .iter()
+            .map(|e| (e.a, e.b, e.start, e.end, e.closest, e.at))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![(1, 2, 1, 3, 1, 2), (0, 1, 2, 2, 0, 2), (0, 2, 2, 2, 1, 2)]
+        );
+
+        let sim = Fleet::new(vec![Nav::default(), Nav::default().with_y(1)]).simulate();
+        assert!(sim.encounters.is_empty());
+        let sim = Fleet::new(vec![Nav::default(), Nav::default()]).simulate();
+        assert_eq!((sim.steps, sim.encounters.len()), (0, 1));
+        assert!(Fleet::default().simulate().encounters.is_empty());
+    }
+
+    #[test]
+    fn test_hold() {
+        let sub = |raw: &str, start: Coord, y: i32| {
+            Nav::new(parse_lines(raw).unwrap(), start)
+                .with_mode(NavMode::Direct)
+                .with_y(y)
+        };
+        let fleet = Fleet::new(vec![
+            // waits three steps before moving.
+            sub("hold 3\nforward 2", Coord(0, 0), 0),
+            sub("back 1\nhold 0\nback 1\nback 1", Coord(5, 0), 0),
+            // beside the meeting point.
+            sub("hold 4", Coord(2, 0), 1),
+        ]);
+
+        let sim = fleet.simulate();
+        assert_eq!(sim.steps, 4);
+        assert_eq!(sim.encounters.len(), 1);
+        assert_eq!((sim.encounters[0].a, sim.encounters[0].b), (0, 1));
+        assert_eq!(sim.encounters[0].at, 4);
+
+        let runs: Vec<_> = fleet
+            .with_threshold(1)
+            .simulate()
+            .encounters
+            .iter()
+            .map(|e| (e.a, e.b, e.start, e.end, e.closest, e.at))
+            .collect();
+        assert_eq!(
+            runs,
+            vec![(1, 2, 3, 4, 1, 3), (0, 1, 4, 4, 0, 4), (0, 2, 4, 4, 1, 4)]
+        );
+    }
+}
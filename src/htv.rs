@@ -1,7 +1,9 @@
 /// Hypothermal Vents navigation system
-use std::{cmp::max, cmp::min, collections::HashMap, fmt::Display, ops::Range, str::FromStr};
+use std::{cmp::max, cmp::min, collections::HashMap, fmt::Display, str::FromStr};
 
-use anyhow::Result;
+use crate::error::{parse_lines, Error, ParseError, Result};
+use crate::solution::{Answer, Solution};
+use crate::trace::{event, Level};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Coord {
@@ -9,6 +11,16 @@ pub struct Coord {
     y: i32,
 }
 
+impl Coord {
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+}
+
 impl Display for Coord {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}, {}", self.x, self.y)
@@ -25,20 +37,32 @@ impl From<(i32, i32)> for Coord {
 }
 
 impl FromStr for Coord {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(',');
-        let x = parts
-            .next()
-            .ok_or(std::io::Error::from(std::io::ErrorKind::InvalidData))?;
-        let x = i32::from_str_radix(x, 10)?;
-        let y = parts
-            .next()
-            .ok_or(std::io::Error::from(std::io::ErrorKind::InvalidData))?;
-        let y = i32::from_str_radix(y, 10)?;
-        Ok(Self::from((x, y)))
+        parse_coord(s, s)
+    }
+}
+
+/// Parses the `x,y` coordinate `s`, which is a slice of `line`, so that errors
+/// point at their position in the whole line.
+fn parse_coord(line: &str, s: &str) -> Result<Coord> {
+    let mut parts = s.split(',');
+    // `split` always yields at least one, possibly empty, part.
+    let x = parts.next().unwrap_or_default();
+    let x = x
+        .parse::<i32>()
+        .map_err(|_| ParseError::new(line, x, "invalid x coordinate"))?;
+    let y = parts
+        .next()
+        .ok_or_else(|| ParseError::new(line, &s[s.len()..], "expected `,` and a y coordinate"))?;
+    let y = y
+        .parse::<i32>()
+        .map_err(|_| ParseError::new(line, y, "invalid y coordinate"))?;
+    if let Some(extra) = parts.next() {
+        return Err(ParseError::new(line, extra, "unexpected token").into());
     }
+    Ok(Coord::from((x, y)))
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -54,24 +78,39 @@ impl Display for Vector {
 }
 
 impl FromStr for Vector {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split(" -> ");
-        let from = parts
-            .next()
-            .ok_or(std::io::Error::from(std::io::ErrorKind::InvalidData))?;
-        let from = Coord::from_str(from)?;
+        let from = parts.next().unwrap_or_default();
+        let from = parse_coord(s, from)?;
         let to = parts
             .next()
-            .ok_or(std::io::Error::from(std::io::ErrorKind::InvalidData))?;
-        let to = Coord::from_str(to)?;
+            .ok_or_else(|| ParseError::new(s, &s[s.len()..], "expected ` -> ` and a coordinate"))?;
+        let to = parse_coord(s, to)?;
 
         Ok(Self { from, to })
     }
 }
 
 impl Vector {
+    pub fn new(from: Coord, to: Coord) -> Self {
+        Self { from, to }
+    }
+
+    pub fn from(&self) -> Coord {
+        self.from
+    }
+
+    pub fn to(&self) -> Coord {
+        self.to
+    }
+
+    /// Returns true if the Vector moves on both the X and Y axis.
+    pub fn is_diagonal(&self) -> bool {
+        self.from.x != self.to.x && self.from.y != self.to.y
+    }
+
     /// Expands a Vector v into a list of Coordiates covered by the Vector.
     pub fn expand(v: Vector) -> Vec<Coord> {
         if v.from == v.to {
@@ -97,22 +136,29 @@ impl Vector {
             }
         } else if (v.from.y - v.to.y).abs() == (v.from.x - v.to.x).abs() {
             // "perfect" diagonal
-            let mut range_x = v.from.x..v.to.x + 1;
-            if range_x.is_empty() {
-                range_x = Range::from((v.to.x..v.from.x + 1).rev());
-            }
-
-            let mut range_y = v.from.y..v.to.y + 1;
-            if range_y.is_empty() {
-                range_y = (v.to.y..v.from.y + 1).rev();
-            }
+            let range_x: Vec<i32> = if v.from.x <= v.to.x {
+                (v.from.x..v.to.x + 1).collect()
+            } else {
+                (v.to.x..v.from.x + 1).rev().collect()
+            };
+
+            let range_y: Vec<i32> = if v.from.y <= v.to.y {
+                (v.from.y..v.to.y + 1).collect()
+            } else {
+                (v.to.y..v.from.y + 1).rev().collect()
+            };
 
             coords = range_x
+                .into_iter()
                 .zip(range_y)
                 .map(|(x, y)| Coord::from((x, y)))
                 .collect();
         } else {
-            println!("{} is a diagonal", v);
+            event!(
+                Level::Debug,
+                "skipping {}, which is not a perfect diagonal",
+                v
+            );
         }
 
         coords
@@ -123,6 +169,12 @@ pub struct CartographicMap {
     pub inner: HashMap<Coord, i32>,
 }
 
+impl From<HashMap<Coord, i32>> for CartographicMap {
+    fn from(inner: HashMap<Coord, i32>) -> Self {
+        Self { inner }
+    }
+}
+
 impl Display for CartographicMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let width: usize = self
@@ -176,9 +228,31 @@ pub fn danger_zone(vectors: HashMap<Coord, i32>) -> i32 {
     vectors.into_values().filter(|v| *v > 1).count() as i32
 }
 
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u8 = 5;
+    type Input = Vec<Vector>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input)
+    }
+
+    /// The first part only considers horizontal and vertical lines.
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let straight = input.iter().filter(|v| !v.is_diagonal()).copied().collect();
+        Ok(danger_zone(intersections(straight)).into())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(danger_zone(intersections(input.clone())).into())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::error::parse_err;
 
     #[test]
     fn test_coord() {
@@ -310,13 +384,28 @@ mod test {
             .map(|line| Vector::from_str(line).unwrap())
             .collect();
 
-        println!("{:?}", vecs);
-
-        let inters = intersections(vecs);
+        // The first part only considers horizontal and vertical lines.
+        let straight: Vec<Vector> = vecs.iter().filter(|v| !v.is_diagonal()).copied().collect();
+        let inters = intersections(straight);
         assert_eq!(inters.len(), 21);
         assert_eq!(inters.clone().into_values().filter(|v| *v > 1).count(), 5);
 
         let danger = danger_zone(inters);
         assert_eq!(danger, 5);
+
+        let danger = danger_zone(intersections(vecs));
+        assert_eq!(danger, 12);
+    }
+
+    #[test]
+    fn test_vector_errors() {
+        let e = parse_err(Vector::from_str("0,9 -> 5;9"));
+        assert_eq!(e.column, 8);
+        assert_eq!(e.token, "5;9");
+
+        let e = parse_err(Day5::parse("0,9 -> 5,9\n8,0 - 0,8"));
+        assert_eq!(e.line, 2);
+        assert_eq!(e.column, 3);
+        assert_eq!(e.token, "0 - 0");
     }
 }
@@ -0,0 +1,25 @@
+pub mod anomaly;
+pub mod autopilot;
+pub mod bench;
+pub mod bingo;
+pub mod compare;
+pub mod diagnostics;
+pub mod envelope;
+pub mod error;
+pub mod fetch;
+pub mod fleet;
+pub mod htv;
+#[cfg(test)]
+mod mock;
+pub mod optimize;
+pub mod profile;
+pub mod readings;
+pub mod report;
+pub mod script;
+pub mod solution;
+pub mod sonar;
+pub mod submarine;
+pub mod submit;
+pub mod trace;
+pub mod verify;
+pub mod window;
@@ -1,67 +1,437 @@
-mod bingo;
-mod diagnostics;
-mod htv;
-mod submarine;
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::Instant,
+};
 
-use std::str::FromStr;
+use anyhow::{anyhow, Context, Result};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Args, Parser, Subcommand,
+};
 
-use anyhow::Result;
+use aoc2021::bench;
+use aoc2021::compare::{self, Comparer};
+use aoc2021::fetch::{self, Connection, InputManager};
+use aoc2021::profile::{self, Svg};
+use aoc2021::report::{self, Format, Record};
+use aoc2021::solution::{self, Part};
+use aoc2021::sonar;
+use aoc2021::submit::Submitter;
+use aoc2021::trace::{self, Filter, StderrSink};
+use aoc2021::verify::{self, Answers};
 
-use crate::htv::*;
+/// Directory holding the committed puzzle inputs.
+const INPUTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/inputs");
 
-fn main() {
-    const DATA: &str = include_str!("./inputs/day5task1.txt");
+/// Directory holding the downloaded puzzle inputs.
+const CACHE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/.cache");
 
-    let vecs: Vec<Vector> = DATA.lines().map(|line| Vector::from_str(line).unwrap()).collect();
-    let danger = danger_zone(intersections(vecs));
-    println!("{:?}", danger);
+#[derive(Parser)]
+#[command(version, about = "Advent of Code 2021")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Print traces to stderr, filtered by level and module, e.g.
+    /// `debug,diagnostics=trace`.
+    #[arg(long, global = true, env = "AOC_TRACE")]
+    trace: Option<Filter>,
 }
 
-#[allow(dead_code)]
-fn str_to_num_vec(s: &str) -> Result<Vec<i32>> {
-    let mut res: Vec<i32> = vec![];
-    for l in s.lines() {
-        let n = l.parse::<i32>()?;
-        res.push(n);
-    }
-    Ok(res)
+#[derive(Subcommand)]
+enum Command {
+    /// Run the solutions and print their answers.
+    Run {
+        /// Day of the puzzle, from 1 to 25. Defaults to every day.
+        #[arg(short, long)]
+        day: Option<u8>,
+
+        /// Part of the puzzle, either 1 or 2. Defaults to both parts.
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: Option<u8>,
+
+        /// Path to the puzzle input, or `-` to read it from stdin. Defaults to
+        /// the committed input for that day, or else to the downloaded one.
+        #[arg(short, long, requires = "day")]
+        input: Option<String>,
+
+        /// Output format.
+        #[arg(
+            short,
+            long,
+            default_value = "table",
+            value_parser = PossibleValuesParser::new(["table", "json", "tsv"])
+                .map(|s| Format::from_str(&s).expect("format is one of the possible values")),
+        )]
+        format: Format,
+
+        #[command(flatten)]
+        remote: Remote,
+    },
+    /// Download the input of a day, unless it is already cached.
+    Fetch {
+        /// Day of the puzzle, from 1 to 25.
+        #[arg(short, long)]
+        day: u8,
+
+        #[command(flatten)]
+        remote: Remote,
+    },
+    /// Submit the answer of a day and part to the puzzle website.
+    Submit {
+        /// Day of the puzzle, from 1 to 25.
+        #[arg(short, long)]
+        day: u8,
+
+        /// Part of the puzzle, either 1 or 2.
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+
+        /// Path to the puzzle input, or `-` to read it from stdin. Defaults to
+        /// the committed input for that day, or else to the downloaded one.
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Path to the history of submitted answers. Defaults to
+        /// `submissions.tsv` in the cache directory.
+        #[arg(long)]
+        history: Option<PathBuf>,
+
+        #[command(flatten)]
+        remote: Remote,
+    },
+    /// Check every solution against the recorded answers for the committed
+    /// inputs.
+    Verify {
+        /// Path to the recorded answers. Defaults to the committed answers.
+        #[arg(short, long)]
+        answers: Option<PathBuf>,
+
+        /// Directory holding the puzzle inputs.
+        #[arg(long, default_value = INPUTS_DIR)]
+        inputs: PathBuf,
+    },
+    /// Time the parse, part 1 and part 2 stages of each day.
+    Bench {
+        /// Only benchmark this day. Defaults to every day.
+        #[arg(short, long)]
+        day: Option<u8>,
+
+        /// Number of times each stage is run.
+        #[arg(short = 'n', long, default_value_t = 10)]
+        iterations: usize,
+
+        /// Write a JSON report to this path.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Directory holding the puzzle inputs.
+        #[arg(long, default_value = INPUTS_DIR)]
+        inputs: PathBuf,
+    },
+    /// Chart the depths of a Sonar Sweep report, as for day 1.
+    Profile {
+        /// Path to the depths, or `-` to read them from stdin. Defaults to the
+        /// input of day 1.
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Number of columns of the chart.
+        #[arg(short, long, default_value_t = 80)]
+        width: usize,
+
+        /// Number of rows of the chart.
+        #[arg(long, default_value_t = 12)]
+        height: usize,
+
+        /// Also write an SVG line chart to this path.
+        #[arg(long)]
+        svg: Option<PathBuf>,
+
+        /// Size of the moving windows overlaid on the SVG chart, 0 to leave
+        /// them out.
+        #[arg(long, default_value_t = 3)]
+        window: usize,
+
+        #[command(flatten)]
+        remote: Remote,
+    },
+    /// Align two Sonar Sweep reports of the same area and diff their depths.
+    Compare {
+        /// Path to the first report.
+        a: PathBuf,
+
+        /// Path to the second report.
+        b: PathBuf,
+
+        /// Largest delta at which two aligned depths still agree.
+        #[arg(short, long, default_value_t = 10.0)]
+        threshold: f64,
+
+        /// Largest shift between the reports tried in either direction.
+        #[arg(long, default_value_t = 10)]
+        max_offset: usize,
+
+        /// Size of the windows averaged to align noisy reports.
+        #[arg(long, default_value_t = 1)]
+        smoothing: usize,
+    },
+}
+
+/// Connection to the puzzle website.
+#[derive(Args)]
+struct Remote {
+    /// Base URL of the puzzle website.
+    #[arg(long, env = "AOC_BASE_URL", default_value = fetch::DEFAULT_BASE_URL)]
+    base_url: String,
+
+    /// Session token of a logged in user.
+    #[arg(long, env = "AOC_SESSION", hide_env_values = true)]
+    session: Option<String>,
+
+    /// Directory holding the downloaded puzzle inputs.
+    #[arg(long, env = "AOC_CACHE_DIR", default_value = CACHE_DIR)]
+    cache_dir: PathBuf,
 }
 
-/// Day 1 - part 1
-#[allow(dead_code)]
-fn num_increase(data: Vec<i32>) -> i32 {
-    let mut sum: i32 = 0;
-    for curr in data.windows(2) {
-        if curr[0] < curr[1] {
-            sum += 1;
+impl Remote {
+    fn connection(&self) -> Connection {
+        let connection = Connection::default().with_base_url(&self.base_url);
+        match &self.session {
+            Some(session) => connection.with_session(session),
+            None => connection,
         }
     }
-    sum
-}
 
-/// Day 1 - part 2
-#[allow(dead_code)]
-fn moving_window(data: Vec<i32>, size: usize) -> Vec<i32> {
-    let mut res: Vec<i32> = vec![];
-    for w in data.windows(size) {
-        res.push(w.iter().sum());
+    fn inputs(&self) -> InputManager {
+        InputManager::new(&self.cache_dir).with_connection(self.connection())
+    }
+
+    fn submitter(&self, history: Option<PathBuf>) -> Submitter {
+        let history = history.unwrap_or_else(|| self.cache_dir.join("submissions.tsv"));
+        Submitter::new(history).with_connection(self.connection())
     }
-    res
 }
 
-/// Day 2 - part 1
-#[allow(dead_code)]
-fn parse_directions(data: &str) -> Vec<Vector> {
-    let mut moves: Vec<Vector> = vec![];
-    for l in data.lines() {
-        let v = Vector::from_str(l).unwrap();
-        moves.push(v);
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(filter) = cli.trace {
+        trace::set_global(StderrSink, filter);
+    }
+
+    match cli.command {
+        Command::Run {
+            day,
+            part,
+            input,
+            format,
+            remote,
+        } => {
+            let puzzles: Vec<_> =
+                match day {
+                    Some(day) => vec![solution::find(day)
+                        .ok_or_else(|| anyhow!("no solution for day {}", day))?],
+                    None => solution::registry().to_vec(),
+                };
+            let parts = match part {
+                Some(part) => vec![Part::try_from(part)?],
+                None => vec![Part::One, Part::Two],
+            };
+
+            let mut records = vec![];
+            for puzzle in puzzles {
+                let data = read_input(puzzle.day(), input.as_deref(), &remote)?;
+                for &part in &parts {
+                    let start = Instant::now();
+                    let answer = puzzle.run(&data, part)?;
+                    records.push(Record {
+                        day: puzzle.day(),
+                        part,
+                        answer,
+                        elapsed: start.elapsed(),
+                    });
+                }
+            }
+            print!("{}", report::render(&records, format));
+        }
+        Command::Fetch { day, remote } => {
+            let inputs = remote.inputs();
+            inputs.get(day)?;
+            println!("{}", inputs.cache_path(day).display());
+        }
+        Command::Submit {
+            day,
+            part,
+            input,
+            history,
+            remote,
+        } => {
+            let puzzle =
+                solution::find(day).ok_or_else(|| anyhow!("no solution for day {}", day))?;
+            let data = read_input(day, input.as_deref(), &remote)?;
+            let part = Part::try_from(part)?;
+            let answer = puzzle.run(&data, part)?;
+            let outcome = remote.submitter(history).submit(day, part, answer)?;
+            println!("{}: {}", answer, outcome);
+        }
+        Command::Verify { answers, inputs } => {
+            let answers = answers.unwrap_or_else(|| Path::new(INPUTS_DIR).join("answers.txt"));
+            let raw = fs::read_to_string(&answers)
+                .with_context(|| format!("failed to read answers {}", answers.display()))?;
+            let answers = Answers::from_str(&raw)?;
+
+            let (mut passed, mut failed, mut missing) = (0, 0, 0);
+            for puzzle in solution::registry() {
+                let input = fs::read_to_string(input_path(&inputs, puzzle.day())).ok();
+                for check in verify::check(*puzzle, input.as_deref(), &answers) {
+                    println!(
+                        "day {:>2} part {}  {:<40} {:>10.3?}",
+                        check.day,
+                        check.part,
+                        check.status.to_string(),
+                        check.elapsed
+                    );
+                    match check.status {
+                        verify::Status::Pass => passed += 1,
+                        verify::Status::Missing => missing += 1,
+                        _ => failed += 1,
+                    }
+                }
+            }
+
+            println!("{} passed, {} failed, {} missing", passed, failed, missing);
+            if failed > 0 {
+                process::exit(1);
+            }
+        }
+        Command::Bench {
+            day,
+            iterations,
+            output,
+            inputs,
+        } => {
+            let puzzles: Vec<_> =
+                match day {
+                    Some(day) => vec![solution::find(day)
+                        .ok_or_else(|| anyhow!("no solution for day {}", day))?],
+                    None => solution::registry().to_vec(),
+                };
+
+            println!(
+                "{:<6} {:>30} {:>30} {:>30}",
+                "day", "parse (min/median/max)", "part 1", "part 2"
+            );
+            let mut reports = vec![];
+            for puzzle in puzzles {
+                let path = input_path(&inputs, puzzle.day());
+                let input = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read input {}", path.display()))?;
+                let report = bench::bench(puzzle, &input, iterations)?;
+                println!(
+                    "{:<6} {:>30} {:>30} {:>30}",
+                    report.day,
+                    format_stats(&report.parse),
+                    format_stats(&report.part1),
+                    format_stats(&report.part2)
+                );
+                reports.push(report);
+            }
+
+            if let Some(output) = output {
+                fs::write(&output, bench::to_json(&reports))
+                    .with_context(|| format!("failed to write report {}", output.display()))?;
+            }
+        }
+        Command::Profile {
+            input,
+            width,
+            height,
+            svg,
+            window,
+            remote,
+        } => {
+            let depths: Vec<i64> =
+                sonar::str_to_num_vec(&read_input(1, input.as_deref(), &remote)?)?;
+            println!("{}", profile::sparkline(&depths, width));
+            print!("{}", profile::ascii_chart(&depths, width, height));
+
+            if let Some(path) = svg {
+                let chart = Svg::default()
+                    .with_window(Some(window).filter(|&w| w > 0))
+                    .render(&depths)?;
+                fs::write(&path, chart)
+                    .with_context(|| format!("failed to write chart {}", path.display()))?;
+            }
+        }
+        Command::Compare {
+            a,
+            b,
+            threshold,
+            max_offset,
+            smoothing,
+        } => {
+            let read = |path: &Path| {
+                fs::read_to_string(path)
+                    .with_context(|| format!("failed to read report {}", path.display()))
+            };
+            let comparer = Comparer::default()
+                .with_threshold(threshold)
+                .with_max_offset(max_offset)
+                .with_smoothing(smoothing);
+            let cmp = compare::compare_str(&read(&a)?, &read(&b)?, &comparer)?;
+
+            match cmp.correlation {
+                Some(r) => println!("offset {} (correlation {:.3})", cmp.offset, r),
+                None => println!("offset {} (no correlation)", cmp.offset),
+            }
+            println!(
+                "{} aligned depths, {} disagreeing regions",
+                cmp.deltas.len(),
+                cmp.regions.len()
+            );
+            for r in &cmp.regions {
+                println!("{:>6}..={:<6} peak {:+}", r.start, r.end, r.peak);
+            }
+        }
     }
-    moves
+
+    Ok(())
+}
+
+fn format_stats(stats: &bench::Stats) -> String {
+    format!("{:.1?}/{:.1?}/{:.1?}", stats.min, stats.median, stats.max)
+}
+
+/// Path of the committed input of the given day.
+fn input_path(dir: &Path, day: u8) -> PathBuf {
+    dir.join(format!("day{}task1.txt", day))
 }
 
-/// Day 2 - part 1
-#[allow(dead_code)]
-fn calc_depth_product(x: i32, y: i32) -> i32 {
-    x * y
+/// Reads the puzzle input from `path`, from stdin if `path` is `-`, or from
+/// the committed input of the given day if no path is given. Days without a
+/// committed input are downloaded instead.
+fn read_input(day: u8, path: Option<&str>, remote: &Remote) -> Result<String> {
+    match path {
+        Some("-") => {
+            let mut buf = String::new();
+            io::stdin().read_to_string(&mut buf)?;
+            Ok(buf)
+        }
+        Some(path) => {
+            fs::read_to_string(path).with_context(|| format!("failed to read input {}", path))
+        }
+        None => {
+            let path = input_path(Path::new(INPUTS_DIR), day);
+            if path.exists() {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read input {}", path.display()))
+            } else {
+                Ok(remote.inputs().get(day)?)
+            }
+        }
+    }
 }
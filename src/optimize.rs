@@ -0,0 +1,280 @@
+/// Shortening of navigation logs without changing where they end
+///
+/// Commands moving along the same axis merge into one, e.g. `down 3`,
+/// `down 5` and `up 2` into `down 6`, and commands that cancel out or have no
+/// effect are dropped. Commands may only be merged across others they commute
+/// with: under `NavMode::Aim`, `forward` dives along the current aim, so
+/// `up` and `down` cannot cross it, while under `NavMode::Direct` they can.
+use crate::submarine::{Direction, EndState, Nav, NavMode, Vector};
+use crate::trace::{event, Level};
+
+/// Result of `optimize`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optimized {
+    pub commands: Vec<Vector>,
+    /// Number of commands before optimizing.
+    pub original: usize,
+    /// State after following either the original or the optimized commands.
+    pub end: EndState,
+}
+
+impl Optimized {
+    /// Commands left per original command, 1 for an empty log.
+    pub fn ratio(&self) -> f64 {
+        match self.original {
+            0 => 1.0,
+            n => self.commands.len() as f64 / n as f64,
+        }
+    }
+}
+
+/// Rewrites the commands of `nav` into as few as possible, then follows both
+/// to check that they end in the same state. The rewrites hold from any
+/// start, but an envelope clamping or rejecting commands may tell them
+/// apart, in which case the original commands are kept.
+pub fn optimize(nav: &Nav) -> Optimized {
+    let mut commands = nav.directions().to_vec();
+    loop {
+        // dropping a command may bring together two that now merge.
+        let shorter = pass(&commands, nav.mode());
+        let done = shorter.len() == commands.len();
+        commands = shorter;
+        if done {
+            break;
+        }
+    }
+
+    let end = nav.clone().follow();
+    let optimized = nav.clone().with_directions(commands.clone()).follow();
+    if optimized != end {
+        event!(
+            Level::Warn,
+            "optimized commands end at {:?} rather than {:?}, keeping the original ones",
+            optimized,
+            end
+        );
+        commands = nav.directions().to_vec();
+    }
+    event!(
+        Level::Debug,
+        "optimized {} commands into {}",
+        nav.directions().len(),
+        commands.len()
+    );
+    Optimized {
+        commands,
+        original: nav.directions().len(),
+        end,
+    }
+}
+
+/// Adds each command to the output, merging it into the last one along the
+/// same axis if every command in between commutes with it.
+fn pass(commands: &[Vector], mode: NavMode) -> Vec<Vector> {
+    let mut out: Vec<Vector> = vec![];
+    'next: for v in commands {
+        let v = match axis(v) {
+            Some((axis, n)) => match command(axis, n) {
+                Some(v) => v,
+                None => continue,
+            },
+            None if v.dir() == Direction::Hold => continue,
+            None => v.clone(),
+        };
+
+        for i in (0..out.len()).rev() {
+            let w = &out[i];
+            if v.dir() == Direction::Surface
+                && matches!(
+                    w.dir(),
+                    Direction::Up | Direction::Down | Direction::Surface
+                )
+            {
+                // resets the depth and aim they set.
+                out.remove(i);
+                continue;
+            }
+            if let Some(merged) = merge(w, &v) {
+                match merged {
+                    Some(merged) => out[i] = merged,
+                    None => {
+                        out.remove(i);
+                    }
+                }
+                continue 'next;
+            }
+            if !commute(w.dir(), v.dir(), mode) {
+                break;
+            }
+        }
+        out.push(v);
+    }
+    out
+}
+
+/// Moves along which commands add up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Forward,
+    Down,
+    Strafe,
+    /// Counterclockwise.
+    Turn,
+}
+
+/// Axis of `v` and the signed amount it moves along it, `None` for commands
+/// that don't add up or whose amount overflows.
+fn axis(v: &Vector) -> Option<(Axis, i32)> {
+    match v.dir() {
+        Direction::Forward => Some((Axis::Forward, v.length())),
+        Direction::Back => Some((Axis::Forward, v.length().checked_neg()?)),
+        Direction::Down => Some((Axis::Down, v.length())),
+        Direction::Up => Some((Axis::Down, v.length().checked_neg()?)),
+        Direction::Strafe => Some((Axis::Strafe, v.length())),
+        Direction::TurnLeft => Some((Axis::Turn, v.length())),
+        Direction::TurnRight => Some((Axis::Turn, v.length().checked_neg()?)),
+        Direction::Surface | Direction::Hold => None,
+    }
+}
+
+/// Shortest command moving `n` along `axis`, `None` if it has no effect.
+fn command(axis: Axis, n: i32) -> Option<Vector> {
+    let signed = |pos: Direction, neg: Direction| match n.checked_neg() {
+        Some(m) if n < 0 => Vector::new(neg, m),
+        _ => Vector::new(pos, n),
+    };
+    match axis {
+        _ if n == 0 => None,
+        Axis::Forward => Some(signed(Direction::Forward, Direction::Back)),
+        Axis::Down => Some(signed(Direction::Down, Direction::Up)),
+        Axis::Strafe => Some(Vector::new(Direction::Strafe, n)),
+        Axis::Turn => match n.rem_euclid(360) {
+            0 => None,
+            270 => Some(Vector::new(Direction::TurnRight, 90)),
+            d => Some(Vector::new(Direction::TurnLeft, d)),
+        },
+    }
+}
+
+/// Single command equivalent to `a` then `b`, or `Some(None)` if they cancel
+/// out. `None` if they don't merge.
+fn merge(a: &Vector, b: &Vector) -> Option<Option<Vector>> {
+    let ((x, m), (y, n)) = (axis(a)?, axis(b)?);
+    if x != y {
+        return None;
+    }
+    Some(command(x, m.checked_add(n)?))
+}
+
+/// Whether following `a` then `b` ends in the same state as `b` then `a`.
+fn commute(a: Direction, b: Direction, mode: NavMode) -> bool {
+    use Direction::*;
+    match (a, b) {
+        (Surface, Up | Down) | (Up | Down, Surface) => false,
+        (Forward | Back, Up | Down | Surface) | (Up | Down | Surface, Forward | Back) => {
+            mode == NavMode::Direct
+        }
+        (TurnLeft | TurnRight, Forward | Back | Strafe)
+        | (Forward | Back | Strafe, TurnLeft | TurnRight) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::envelope::{Envelope, Policy};
+    use crate::error::parse_lines;
+    use crate::submarine::Coord;
+
+    fn nav(raw: &str, mode: NavMode) -> Nav {
+        Nav::from(parse_lines::<Vector>(raw).unwrap()).with_mode(mode)
+    }
+
+    #[test]
+    fn test_optimize() {
+        let opt = optimize(&nav("down 3\ndown 5\nup 2", NavMode::Aim));
+        assert_eq!(opt.commands, vec![Vector::new(Direction::Down, 6)]);
+        assert_eq!(opt.end.aim(), 6);
+
+        // the day 2 example.
+        let example = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+        let opt = optimize(&nav(example, NavMode::Aim));
+        assert_eq!(opt.commands.len(), 5);
+        assert_eq!(opt.end.product(), 900);
+        let opt = optimize(&nav(example, NavMode::Direct));
+        assert_eq!(
+            opt.commands,
+            parse_lines::<Vector>("forward 15\ndown 10").unwrap()
+        );
+        assert_eq!(opt.ratio(), 2.0 / 6.0);
+
+        let opt = optimize(&nav(
+            "turn right 90\nstrafe 2\nturn right 180\nhold 4\nturn left 270\nforward 0\nback 3\ndown 7\nsurface\nsurface",
+            NavMode::Aim,
+        ));
+        assert_eq!(
+            opt.commands,
+            parse_lines::<Vector>("turn right 90\nstrafe 2\nturn left 90\nback 3\nsurface")
+                .unwrap()
+        );
+        assert_eq!(optimize(&Nav::default()).ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_optimize_any_start() {
+        // pseudo-random logs, checked from other starts than the one proven.
+        let dirs = [
+            "forward",
+            "back",
+            "up",
+            "down",
+            "strafe",
+            "turn left",
+            "turn right",
+            "surface",
+            "hold",
+        ];
+        let mut seed = 7u32;
+        let mut next = |n: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % n
+        };
+        for _ in 0..200 {
+            let log: Vec<Vector> = (0..12)
+                .map(|_| {
+                    let dir = dirs[next(dirs.len() as u32) as usize];
+                    match dir {
+                        "surface" => dir.parse().unwrap(),
+                        "turn left" | "turn right" => {
+                            format!("{} {}", dir, 90 * next(4)).parse().unwrap()
+                        }
+                        _ => format!("{} {}", dir, next(4)).parse().unwrap(),
+                    }
+                })
+                .collect();
+            for mode in [NavMode::Aim, NavMode::Direct] {
+                let opt = optimize(&Nav::from(log.clone()).with_mode(mode));
+                assert!(opt.commands.len() <= log.len());
+                let from = |commands: Vec<Vector>| {
+                    Nav::new(commands, Coord(3, 4))
+                        .with_mode(mode)
+                        .with_aim(-2)
+                        .follow()
+                };
+                assert_eq!(from(opt.commands), from(log.clone()), "{:?}", log);
+            }
+        }
+    }
+
+    #[test]
+    fn test_envelope() {
+        // merging into `down 6` would get clamped.
+        let nav = nav("down 3\ndown 3\nforward 1", NavMode::Aim)
+            .with_envelope(Envelope::default().with_max_step(4, Policy::Clamp));
+        let opt = optimize(&nav);
+        assert_eq!(opt.commands, nav.directions());
+        assert_eq!(opt.ratio(), 1.0);
+        assert_eq!(opt.end.depth(), 6);
+    }
+}
@@ -0,0 +1,227 @@
+/// Rendering of Sonar Sweep depth profiles
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::sonar::moving_window;
+use crate::window::Num;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Averages `depths` into at most `width` columns, or keeps one column per
+/// depth if `width` is 0.
+fn columns<T: Num>(depths: &[T], width: usize) -> Vec<f64> {
+    if width == 0 || depths.len() <= width {
+        return depths.iter().map(|d| d.to_f64()).collect();
+    }
+    let chunk = depths.len().div_ceil(width);
+    depths
+        .chunks(chunk)
+        .map(|c| c.iter().map(|d| d.to_f64()).sum::<f64>() / c.len() as f64)
+        .collect()
+}
+
+/// Smallest and largest of `values`, `(0, 0)` if there are none.
+fn bounds(values: &[f64]) -> (f64, f64) {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if min > max {
+        (0.0, 0.0)
+    } else {
+        (min, max)
+    }
+}
+
+/// Position of `v` between `min` and `max`, from 0 to 1.
+fn scale(v: f64, (min, max): (f64, f64)) -> f64 {
+    if max > min {
+        (v - min) / (max - min)
+    } else {
+        0.5
+    }
+}
+
+/// One bar per column, taller for deeper readings.
+pub fn sparkline<T: Num>(depths: &[T], width: usize) -> String {
+    let cols = columns(depths, width);
+    let bounds = bounds(&cols);
+    cols.iter()
+        .map(|&v| BARS[(scale(v, bounds) * (BARS.len() - 1) as f64).round() as usize])
+        .collect()
+}
+
+/// Chart of `height` rows with the depth growing downwards, labelled with the
+/// shallowest and deepest depths. Each column is marked `v` when it is deeper
+/// than the previous one, `^` when it is shallower and `-` when it is level.
+pub fn ascii_chart<T: Num>(depths: &[T], width: usize, height: usize) -> String {
+    let cols = columns(depths, width);
+    let bounds = bounds(&cols);
+    let height = height.max(2);
+    let mut grid = vec![vec![' '; cols.len()]; height];
+    for (i, &v) in cols.iter().enumerate() {
+        let row = (scale(v, bounds) * (height - 1) as f64).round() as usize;
+        grid[row][i] = match i.checked_sub(1).map(|p| cols[p]) {
+            None => '*',
+            Some(prev) if v > prev => 'v',
+            Some(prev) if v < prev => '^',
+            Some(_) => '-',
+        };
+    }
+
+    let labels = [format!("{:.0}", bounds.0), format!("{:.0}", bounds.1)];
+    let pad = labels.iter().map(String::len).max().unwrap_or_default();
+    let mut out = String::new();
+    for (r, row) in grid.iter().enumerate() {
+        let label = match r {
+            0 => labels[0].as_str(),
+            r if r == height - 1 => labels[1].as_str(),
+            _ => "",
+        };
+        let row: String = row.iter().collect();
+        let _ = writeln!(out, "{:>pad$} |{}", label, row.trim_end());
+    }
+    out
+}
+
+const INCREASE: &str = "#d62728";
+const DECREASE: &str = "#2ca02c";
+const LEVEL: &str = "#7f7f7f";
+const WINDOW: &str = "#1f77b4";
+
+/// SVG line chart of a depth profile, with the depth growing downwards.
+/// Increases and decreases are drawn in different colours, and the means of
+/// `moving_window` are overlaid as a dashed line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Svg {
+    width: u32,
+    height: u32,
+    window: Option<usize>,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 300,
+            window: Some(3),
+        }
+    }
+}
+
+impl Svg {
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Size of the windows of the overlay, `None` to leave it out.
+    pub fn with_window(mut self, window: Option<usize>) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Fails if a window of the overlay overflows `T`.
+    pub fn render<T: Num>(&self, depths: &[T]) -> Result<String> {
+        const MARGIN: f64 = 10.0;
+        let values: Vec<f64> = depths.iter().map(|d| d.to_f64()).collect();
+        let bounds = bounds(&values);
+        let (w, h) = (self.width as f64, self.height as f64);
+        let step = (w - 2.0 * MARGIN) / values.len().saturating_sub(1).max(1) as f64;
+        let point = |i: f64, v: f64| {
+            (
+                MARGIN + i * step,
+                MARGIN + scale(v, bounds) * (h - 2.0 * MARGIN),
+            )
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+            self.width, self.height
+        );
+        let _ = writeln!(
+            out,
+            "  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+        );
+        for (i, pair) in values.windows(2).enumerate() {
+            let (x1, y1) = point(i as f64, pair[0]);
+            let (x2, y2) = point(i as f64 + 1.0, pair[1]);
+            let colour = if pair[1] > pair[0] {
+                INCREASE
+            } else if pair[1] < pair[0] {
+                DECREASE
+            } else {
+                LEVEL
+            };
+            let _ = writeln!(
+                out,
+                "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\"/>",
+                x1, y1, x2, y2, colour
+            );
+        }
+
+        if let Some(size) = self.window.filter(|&s| s > 0 && s <= depths.len()) {
+            // each mean sits in the middle of its window.
+            let offset = (size - 1) as f64 / 2.0;
+            let points: Vec<String> = moving_window(depths.to_vec(), size)?
+                .iter()
+                .enumerate()
+                .map(|(i, sum)| {
+                    let (x, y) = point(i as f64 + offset, sum.to_f64() / size as f64);
+                    format!("{:.1},{:.1}", x, y)
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-dasharray=\"4 2\"/>",
+                points.join(" "),
+                WINDOW
+            );
+        }
+        out.push_str("</svg>\n");
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DATA: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&DATA, 0), "▁▁▂▂▁▂▅█▇▇");
+        assert_eq!(sparkline(&DATA, 5), "▁▂▁▇█");
+        assert_eq!(sparkline(&[3u64, 3], 0), "▅▅");
+        assert_eq!(sparkline::<i32>(&[], 0), "");
+    }
+
+    #[test]
+    fn test_ascii_chart() {
+        assert_eq!(
+            ascii_chart(&[1, 3, 2, 2], 0, 3),
+            "1 |*
+  |  ^-
+3 | v
+"
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = Svg::default().with_size(100, 50).render(&DATA).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches(INCREASE).count(), 7);
+        assert_eq!(svg.matches(DECREASE).count(), 2);
+        // first mean, of the first three depths, above the second depth.
+        assert!(svg.contains("<polyline points=\"18.9,11.4 "));
+
+        let svg = Svg::default().with_window(None).render(&DATA).unwrap();
+        assert!(!svg.contains("<polyline"));
+        assert!(Svg::default().render(&[i32::MAX, 1, 1]).is_err());
+        assert!(Svg::default().render::<i32>(&[]).is_ok());
+    }
+}
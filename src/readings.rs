@@ -0,0 +1,291 @@
+/// Sonar readings from raw sensor dumps
+///
+/// Besides the one depth per line of the puzzle input, dumps can have
+/// comments starting with `#`, missing readings left blank or marked `N/A`,
+/// and CSV rows holding a timestamp and a sensor id next to the depth.
+/// Depths are read into any `Num`, like the rest of the sonar code.
+use std::str::FromStr;
+
+use crate::error::{ParseError, Result};
+use crate::window::Num;
+
+/// What to do with a missing reading.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    /// Fail on the first missing reading.
+    #[default]
+    Error,
+    /// Leave missing readings out.
+    Skip,
+    /// Fill missing readings linearly from the readings of the same sensor
+    /// around them, rounding to the nearest depth. Missing readings before the
+    /// first or after the last known one of a sensor are left out.
+    Interpolate,
+}
+
+/// Columns of a CSV dump, 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    pub timestamp: Option<usize>,
+    pub depth: usize,
+    pub sensor: Option<usize>,
+}
+
+impl Default for Columns {
+    /// `timestamp,depth,sensor`
+    fn default() -> Self {
+        Self {
+            timestamp: Some(0),
+            depth: 1,
+            sensor: Some(2),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One depth per line, where blank lines are missing readings.
+    #[default]
+    Lines,
+    /// Comma separated values, where blank lines are ignored.
+    Csv { columns: Columns, header: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reading<T> {
+    /// 1-based line of the reading in the dump.
+    pub line: usize,
+    pub timestamp: Option<String>,
+    pub depth: T,
+    /// Whether the reading was missing and filled by interpolation.
+    pub interpolated: bool,
+}
+
+/// Readings of a single sensor, in the order of the dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Series<T> {
+    /// Empty for dumps without a sensor column.
+    pub sensor: String,
+    pub readings: Vec<Reading<T>>,
+}
+
+impl<T: Copy> Series<T> {
+    pub fn depths(&self) -> Vec<T> {
+        self.readings.iter().map(|r| r.depth).collect()
+    }
+}
+
+/// Readings of a sensor with their depth, `None` while missing.
+type Pending<T> = Vec<(Reading<T>, Option<T>)>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reader {
+    layout: Layout,
+    missing: Missing,
+}
+
+impl Reader {
+    pub fn new(layout: Layout) -> Self {
+        Self {
+            layout,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// Reads the readings of every sensor of the dump, ordered by the first
+    /// appearance of the sensor.
+    pub fn read<T: Num + FromStr>(&self, s: &str) -> Result<Vec<Series<T>>> {
+        let mut series: Vec<(String, Pending<T>)> = vec![];
+        let mut header = matches!(self.layout, Layout::Csv { header: true, .. });
+
+        for (i, raw) in s.lines().enumerate() {
+            let n = i + 1;
+            let line = raw.split('#').next().unwrap_or_default();
+            if raw.trim_start().starts_with('#') {
+                continue;
+            }
+
+            let (timestamp, depth, sensor) = match self.layout {
+                Layout::Lines => (None, line.trim(), ""),
+                Layout::Csv { columns, .. } => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    if header {
+                        header = false;
+                        continue;
+                    }
+                    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                    let field = |c: usize| {
+                        fields.get(c).copied().ok_or_else(|| {
+                            ParseError::new(
+                                raw,
+                                &line[line.len()..],
+                                format!("missing column {}", c + 1),
+                            )
+                            .at_line(n)
+                        })
+                    };
+                    (
+                        columns.timestamp.map(field).transpose()?,
+                        field(columns.depth)?,
+                        columns.sensor.map(field).transpose()?.unwrap_or_default(),
+                    )
+                }
+            };
+
+            let value = if depth.is_empty() || depth.eq_ignore_ascii_case("n/a") {
+                match self.missing {
+                    Missing::Error => {
+                        return Err(ParseError::new(raw, depth, "missing depth")
+                            .at_line(n)
+                            .into())
+                    }
+                    Missing::Skip => continue,
+                    Missing::Interpolate => None,
+                }
+            } else {
+                Some(
+                    depth
+                        .parse::<T>()
+                        .map_err(|_| ParseError::new(raw, depth, "invalid depth").at_line(n))?,
+                )
+            };
+
+            let reading = Reading {
+                line: n,
+                timestamp: timestamp.map(str::to_string),
+                depth: T::ZERO,
+                interpolated: value.is_none(),
+            };
+            match series.iter_mut().find(|(s, _)| s == sensor) {
+                Some((_, readings)) => readings.push((reading, value)),
+                None => series.push((sensor.to_string(), vec![(reading, value)])),
+            }
+        }
+
+        Ok(series
+            .into_iter()
+            .map(|(sensor, readings)| Series {
+                sensor,
+                readings: interpolate(readings),
+            })
+            .collect())
+    }
+}
+
+/// Fills the missing depths from the known ones around them, dropping the
+/// ones at either end. The arithmetic is done in `f64`, so interpolated
+/// depths are only as precise as `f64` is for wide types.
+fn interpolate<T: Num>(readings: Pending<T>) -> Vec<Reading<T>> {
+    let known: Vec<(usize, T)> = readings
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, d))| d.map(|d| (i, d)))
+        .collect();
+
+    let mut res = Vec::with_capacity(readings.len());
+    let mut next: usize = 0;
+    for (i, (mut reading, depth)) in readings.into_iter().enumerate() {
+        match depth {
+            Some(d) => {
+                reading.depth = d;
+                next += 1;
+            }
+            None => {
+                let (Some(&(i0, d0)), Some(&(i1, d1))) = (
+                    next.checked_sub(1).and_then(|p| known.get(p)),
+                    known.get(next),
+                ) else {
+                    continue;
+                };
+                let t = (i - i0) as f64 / (i1 - i0) as f64;
+                let (d0, d1) = (d0.to_f64(), d1.to_f64());
+                reading.depth = T::from_f64(d0 + t * (d1 - d0));
+            }
+        }
+        res.push(reading);
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+
+    #[test]
+    fn test_lines() {
+        let dump = "# sweep 1\n199\n\n207 # noisy\nN/A\n215\n";
+        let read = |missing| {
+            Reader::new(Layout::Lines)
+                .with_missing(missing)
+                .read::<i32>(dump)
+        };
+
+        let series = read(Missing::Skip).unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].depths(), vec![199, 207, 215]);
+
+        let series = read(Missing::Interpolate).unwrap();
+        assert_eq!(series[0].depths(), vec![199, 203, 207, 211, 215]);
+        assert!(series[0].readings[1].interpolated);
+        assert_eq!(series[0].readings[1].line, 3);
+
+        let e = parse_err(read(Missing::Error));
+        assert_eq!(e.line, 3);
+        assert_eq!(e.reason, "missing depth");
+    }
+
+    #[test]
+    fn test_csv() {
+        let dump = "time,depth,sensor
+00:00,199,a
+00:00,300,b
+00:01,N/A,a
+00:01,,b
+00:02,201,a
+
+00:02,310,b
+00:03,,b
+";
+        let reader = Reader::new(Layout::Csv {
+            columns: Columns::default(),
+            header: true,
+        })
+        .with_missing(Missing::Interpolate);
+        let series = reader.read::<i32>(dump).unwrap();
+
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].sensor, "a");
+        assert_eq!(series[0].depths(), vec![199, 200, 201]);
+        assert_eq!(series[0].readings[1].timestamp.as_deref(), Some("00:01"));
+        assert_eq!(series[1].sensor, "b");
+        assert_eq!(series[1].depths(), vec![300, 305, 310]);
+
+        let e = parse_err(reader.read::<i32>("00:00,199,a\n00:01,2x0,a\n"));
+        assert_eq!((e.line, e.column), (2, 7));
+        let e = parse_err(reader.read::<i32>("00:00,199,a\n00:01\n"));
+        assert_eq!(e.reason, "missing column 2");
+    }
+
+    #[test]
+    fn test_wide_depths() {
+        let reader = Reader::new(Layout::Lines).with_missing(Missing::Interpolate);
+        let series = reader.read::<i64>("5000000000\n\n5000000010\n").unwrap();
+        assert_eq!(
+            series[0].depths(),
+            vec![5_000_000_000, 5_000_000_005, 5_000_000_010]
+        );
+
+        // decreasing depths don't underflow unsigned types.
+        let series = reader.read::<u64>("10\n\n4\n").unwrap();
+        assert_eq!(series[0].depths(), vec![10, 7, 4]);
+        assert!(reader.read::<i32>("5000000000\n").is_err());
+    }
+}
@@ -0,0 +1,157 @@
+/// Rendering of the results of the solutions
+use std::{fmt::Write, str::FromStr, time::Duration};
+
+use crate::error::{Error, ParseError, Result};
+use crate::solution::{Answer, Part};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub day: u8,
+    pub part: Part,
+    pub answer: Answer,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Aligned columns for humans.
+    #[default]
+    Table,
+    /// An array of objects, with the elapsed time in nanoseconds.
+    Json,
+    /// Tab separated values with a header row, with the elapsed time in
+    /// nanoseconds.
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "tsv" => Ok(Format::Tsv),
+            s => Err(ParseError::new(s, s, "unknown format, expected table, json or tsv").into()),
+        }
+    }
+}
+
+pub fn render(records: &[Record], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Table => {
+            let width = records
+                .iter()
+                .map(|r| r.answer.to_string().len())
+                .chain(["answer".len()])
+                .max()
+                .unwrap_or_default();
+            let _ = writeln!(out, "day  part  {:>width$}  elapsed", "answer");
+            for r in records {
+                let _ = writeln!(
+                    out,
+                    "{:>3}  {:>4}  {:>width$}  {:.1?}",
+                    r.day, r.part, r.answer, r.elapsed
+                );
+            }
+        }
+        Format::Json => {
+            out.push('[');
+            for (i, r) in records.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let _ = write!(
+                    out,
+                    "\n  {{\"day\": {}, \"part\": {}, \"answer\": {}, \"elapsed_ns\": {}}}",
+                    r.day,
+                    r.part,
+                    r.answer,
+                    r.elapsed.as_nanos()
+                );
+            }
+            if !records.is_empty() {
+                out.push('\n');
+            }
+            out.push_str("]\n");
+        }
+        Format::Tsv => {
+            out.push_str("day\tpart\tanswer\telapsed_ns\n");
+            for r in records {
+                let _ = writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}",
+                    r.day,
+                    r.part,
+                    r.answer,
+                    r.elapsed.as_nanos()
+                );
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 1,
+                part: Part::One,
+                answer: 7,
+                elapsed: Duration::from_micros(12),
+            },
+            Record {
+                day: 2,
+                part: Part::Two,
+                answer: 1340836560,
+                elapsed: Duration::from_millis(3),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            render(&records(), Format::Table),
+            "day  part      answer  elapsed
+  1     1           7  12.0µs
+  2     2  1340836560  3.0ms
+"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            render(&records(), Format::Json),
+            "[
+  {\"day\": 1, \"part\": 1, \"answer\": 7, \"elapsed_ns\": 12000},
+  {\"day\": 2, \"part\": 2, \"answer\": 1340836560, \"elapsed_ns\": 3000000}
+]
+"
+        );
+        assert_eq!(render(&[], Format::Json), "[]\n");
+    }
+
+    #[test]
+    fn test_tsv() {
+        assert_eq!(
+            render(&records(), Format::Tsv),
+            "day\tpart\tanswer\telapsed_ns
+1\t1\t7\t12000
+2\t2\t1340836560\t3000000
+"
+        );
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!(Format::from_str("json").unwrap(), Format::Json);
+        assert!(Format::from_str("xml").is_err());
+    }
+}
@@ -0,0 +1,414 @@
+/// Navigation scripts expanding into submarine commands
+///
+/// A script is a list of `submarine::Vector` commands, one per line, with a
+/// few additions:
+///
+/// ```text
+/// # comments run to the end of the line
+/// let step = 5
+/// macro dive {
+///     down $step
+///     forward 2
+/// }
+/// repeat 3 {
+///     dive
+///     forward $step
+/// }
+/// ```
+///
+/// Variables hold integers and are substituted wherever a number is
+/// expected. Macros are defined at the top level, before or after their
+/// calls, and see the variables as they are when called.
+use std::{collections::HashMap, str::FromStr};
+
+use crate::error::{Error, ParseError, Result};
+use crate::submarine::{Direction, Vector};
+
+/// Deepest nesting of macro calls, which stops recursive macros.
+const MAX_CALL_DEPTH: usize = 64;
+
+/// Most commands a script may expand into, which stops huge repeats.
+const MAX_COMMANDS: usize = 1_000_000;
+
+/// Most statements a script may run, which stops huge repeats of `let`s.
+const MAX_STATEMENTS: usize = 4_000_000;
+
+/// Words starting a statement, besides single-word directions, which can't
+/// name a macro.
+const KEYWORDS: [&str; 4] = ["let", "macro", "repeat", "turn"];
+
+/// A line of the script, split in tokens which are slices of `src`.
+#[derive(Debug, Clone)]
+struct Line<'a> {
+    /// 1-based line number.
+    n: usize,
+    src: &'a str,
+    tokens: Vec<&'a str>,
+}
+
+impl<'a> Line<'a> {
+    fn error(&self, token: &str, reason: impl Into<String>) -> Error {
+        ParseError::new(self.src, token, reason)
+            .at_line(self.n)
+            .into()
+    }
+
+    /// Empty slice at the end of the line, for errors about missing tokens.
+    fn end(&self) -> &'a str {
+        &self.src[self.src.len()..]
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Stmt<'a> {
+    Command(Line<'a>),
+    Call(Line<'a>),
+    Let(Line<'a>),
+    Repeat(Line<'a>, Vec<Stmt<'a>>),
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+    macros: HashMap<&'a str, Vec<Stmt<'a>>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        let lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, src)| Line {
+                n: i + 1,
+                src,
+                tokens: src
+                    .split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect(),
+            })
+            .filter(|l| !l.tokens.is_empty())
+            .collect();
+        Self {
+            lines,
+            pos: 0,
+            macros: HashMap::new(),
+        }
+    }
+
+    /// Parses statements up to the `}` closing the block opened on `open`, or
+    /// up to the end of the script at the top level.
+    fn block(&mut self, open: Option<&Line<'a>>) -> Result<Vec<Stmt<'a>>> {
+        let mut stmts = vec![];
+        while let Some(line) = self.lines.get(self.pos).cloned() {
+            self.pos += 1;
+            match line.tokens[..] {
+                ["}"] => {
+                    return match open {
+                        Some(_) => Ok(stmts),
+                        None => Err(line.error(line.tokens[0], "unmatched `}`")),
+                    };
+                }
+                ["repeat", ..] => {
+                    expect_open(&line, 3)?;
+                    let body = self.block(Some(&line))?;
+                    stmts.push(Stmt::Repeat(line, body));
+                }
+                ["macro", ..] => {
+                    expect_open(&line, 3)?;
+                    if open.is_some() {
+                        return Err(
+                            line.error(line.tokens[0], "macros must be defined at the top level")
+                        );
+                    }
+                    let name = line.tokens[1];
+                    check_name(&line, name)?;
+                    if KEYWORDS.contains(&name) {
+                        return Err(line.error(name, "name is reserved"));
+                    }
+                    if Direction::from_str(name).is_ok() || self.macros.contains_key(name) {
+                        return Err(line.error(name, "name is already taken by a command or macro"));
+                    }
+                    let body = self.block(Some(&line))?;
+                    self.macros.insert(name, body);
+                }
+                ["let", ..] => {
+                    match line.tokens[..] {
+                        [_, name, "=", _] => check_name(&line, name)?,
+                        [_, _, eq, ..] if eq != "=" => return Err(line.error(eq, "expected `=`")),
+                        [_, _, _, _, extra, ..] => {
+                            return Err(line.error(extra, "unexpected token"))
+                        }
+                        _ => return Err(line.error(line.end(), "expected `let <name> = <value>`")),
+                    }
+                    stmts.push(Stmt::Let(line));
+                }
+                [word] if Direction::from_str(word).is_err() && !KEYWORDS.contains(&word) => {
+                    stmts.push(Stmt::Call(line))
+                }
+                _ => stmts.push(Stmt::Command(line)),
+            }
+        }
+
+        match open {
+            Some(line) => Err(line.error(line.tokens[line.tokens.len() - 1], "unclosed block")),
+            None => Ok(stmts),
+        }
+    }
+}
+
+/// Checks that `line` is `<keyword> <argument> {`, with `len` tokens.
+fn expect_open(line: &Line, len: usize) -> Result<()> {
+    match line.tokens.get(len - 1) {
+        Some(&"{") => match line.tokens.get(len) {
+            Some(extra) => Err(line.error(extra, "unexpected token")),
+            None => Ok(()),
+        },
+        Some(t) => Err(line.error(t, "expected `{`")),
+        None => Err(line.error(line.end(), "expected `{`")),
+    }
+}
+
+fn check_name(line: &Line, name: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(line.error(name, "invalid name"))
+    }
+}
+
+struct Expander<'a, 'p> {
+    macros: &'p HashMap<&'a str, Vec<Stmt<'a>>>,
+    vars: HashMap<&'a str, i32>,
+    /// Number of `let`s that changed a variable so far.
+    writes: usize,
+    /// Number of statements run so far.
+    statements: usize,
+    out: Vec<Vector>,
+}
+
+impl<'a> Expander<'a, '_> {
+    /// Value of `token`, either a number or a `$variable`.
+    fn value(&self, line: &Line, token: &str) -> Result<i32> {
+        match token.strip_prefix('$') {
+            Some(name) => self
+                .vars
+                .get(name)
+                .copied()
+                .ok_or_else(|| line.error(token, "undefined variable")),
+            None => token
+                .parse::<i32>()
+                .map_err(|_| line.error(token, "invalid number")),
+        }
+    }
+
+    fn run(&mut self, stmts: &[Stmt<'a>], depth: usize) -> Result<()> {
+        for stmt in stmts {
+            self.statements += 1;
+            if self.statements > MAX_STATEMENTS {
+                let line = match stmt {
+                    Stmt::Command(line)
+                    | Stmt::Call(line)
+                    | Stmt::Let(line)
+                    | Stmt::Repeat(line, _) => line,
+                };
+                let reason = format!("script runs more than {} statements", MAX_STATEMENTS);
+                return Err(line.error(line.tokens[0], reason));
+            }
+            match stmt {
+                Stmt::Let(line) => {
+                    let value = self.value(line, line.tokens[3])?;
+                    if self.vars.insert(line.tokens[1], value) != Some(value) {
+                        self.writes += 1;
+                    }
+                }
+                Stmt::Repeat(line, body) => {
+                    let count = self.value(line, line.tokens[1])?;
+                    if count < 0 {
+                        return Err(
+                            line.error(line.tokens[1], "cannot repeat a negative number of times")
+                        );
+                    }
+                    for _ in 0..count {
+                        let before = (self.out.len(), self.writes);
+                        self.run(body, depth)?;
+                        // the other iterations would do nothing either.
+                        if (self.out.len(), self.writes) == before {
+                            break;
+                        }
+                    }
+                }
+                Stmt::Call(line) => {
+                    let name = line.tokens[0];
+                    let body = self
+                        .macros
+                        .get(name)
+                        .ok_or_else(|| line.error(name, "unknown command or macro"))?;
+                    if depth >= MAX_CALL_DEPTH {
+                        return Err(line.error(name, "macro calls are nested too deeply"));
+                    }
+                    self.run(body, depth + 1)?;
+                }
+                Stmt::Command(line) => {
+                    if self.out.len() >= MAX_COMMANDS {
+                        let reason =
+                            format!("script expands into more than {} commands", MAX_COMMANDS);
+                        return Err(line.error(line.tokens[0], reason));
+                    }
+                    self.out.push(self.command(line)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the command on `line` after substituting its variables. Errors
+    /// point at the token of the original line.
+    fn command(&self, line: &Line) -> Result<Vector> {
+        let mut tokens = Vec::with_capacity(line.tokens.len());
+        for t in &line.tokens {
+            tokens.push(match t.starts_with('$') {
+                true => self.value(line, t)?.to_string(),
+                false => t.to_string(),
+            });
+        }
+        let expanded = tokens.join(" ");
+
+        Vector::from_str(&expanded).map_err(|e| match e {
+            Error::Parse(e) => {
+                // column of the start of each token in `expanded`.
+                let mut start = 1;
+                let mut original = line.end();
+                for (t, raw) in tokens.iter().zip(&line.tokens) {
+                    if e.token.is_empty() {
+                        break;
+                    }
+                    if e.column >= start {
+                        original = raw;
+                    }
+                    start += t.chars().count() + 1;
+                }
+                line.error(original, e.reason)
+            }
+            e => e,
+        })
+    }
+}
+
+/// Expands a script into the commands it stands for, ready for `Nav`.
+pub fn expand(s: &str) -> Result<Vec<Vector>> {
+    let mut parser = Parser::new(s);
+    let stmts = parser.block(None)?;
+    let mut expander = Expander {
+        macros: &parser.macros,
+        vars: HashMap::new(),
+        writes: 0,
+        statements: 0,
+        out: vec![],
+    };
+    expander.run(&stmts, 0)?;
+    Ok(expander.out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+    use crate::submarine::Nav;
+
+    #[test]
+    fn test_expand() {
+        let script = "# the day 2 example, looped
+let step = 5
+repeat 2 {
+    forward $step   # cruise
+    dive
+}
+macro dive {
+    down $step
+    let step = 8
+}
+surface
+";
+        let expected =
+            crate::error::parse_lines::<Vector>("forward 5\ndown 5\nforward 8\ndown 8\nsurface");
+        assert_eq!(expand(script).unwrap(), expected.unwrap());
+
+        let end = Nav::from(expand("repeat 3 {\nforward 2\n}\ndown 1").unwrap()).follow();
+        assert_eq!(end.horizontal(), 6);
+        assert!(expand("").unwrap().is_empty());
+        assert!(expand("repeat 2000000000 {\nlet x = 1\n}")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        for (script, line, column, reason) in [
+            ("forward 1\n}", 2, 1, "unmatched `}`"),
+            ("forward 1\nrepeat 2 {\nup 1", 2, 10, "unclosed block"),
+            ("repeat 2\n}", 1, 9, "expected `{`"),
+            (
+                "repeat 2 {\nmacro a {\n}\n}",
+                2,
+                1,
+                "macros must be defined at the top level",
+            ),
+            ("let 2x = 1", 1, 5, "invalid name"),
+            ("let x 1", 1, 7, "expected `=`"),
+            ("forward $depth", 1, 9, "undefined variable"),
+            (
+                "let n = -1\nrepeat $n {\n}",
+                2,
+                8,
+                "cannot repeat a negative number of times",
+            ),
+            ("dive", 1, 1, "unknown command or macro"),
+            (
+                "macro a {\nb\n}\nmacro b {\na\n}\na",
+                5,
+                1,
+                "macro calls are nested too deeply",
+            ),
+            ("let l = 4\nforwrd $l", 2, 1, "unknown direction"),
+            (
+                "let l = 4\nturn left $l",
+                2,
+                11,
+                "turns must be a multiple of 90 degrees",
+            ),
+            ("let l = 4\nforward", 2, 8, "expected a length"),
+            (
+                "repeat 1000 {\nrepeat 1001 {\nforward 1\n}\n}",
+                3,
+                1,
+                "script expands into more than 1000000 commands",
+            ),
+            (
+                "repeat 2000000000 {\nlet x = 1\nlet x = 2\n}",
+                3,
+                1,
+                "script runs more than 4000000 statements",
+            ),
+            ("macro turn {\n}", 1, 7, "name is reserved"),
+            (
+                "macro down {\n}",
+                1,
+                7,
+                "name is already taken by a command or macro",
+            ),
+            ("turn", 1, 5, "expected `left` or `right`"),
+        ] {
+            let e = parse_err(expand(script));
+            assert_eq!(
+                (e.line, e.column, e.reason.as_str()),
+                (line, column, reason),
+                "{}",
+                script
+            );
+        }
+    }
+}
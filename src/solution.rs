@@ -0,0 +1,141 @@
+/// Common shape shared by every day's solution.
+use std::{any::Any, fmt::Display};
+
+use crate::error::{Error, Result};
+use crate::{bingo, diagnostics, htv, sonar, submarine};
+
+/// Answer to one part of a puzzle.
+pub type Answer = i64;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Part::One => f.pad("1"),
+            Part::Two => f.pad("2"),
+        }
+    }
+}
+
+impl TryFrom<u8> for Part {
+    type Error = Error;
+
+    fn try_from(n: u8) -> Result<Self, Self::Error> {
+        match n {
+            1 => Ok(Part::One),
+            2 => Ok(Part::Two),
+            n => Err(Error::InvalidPart(n)),
+        }
+    }
+}
+
+/// A day's solution, split into a parse stage shared by both parts and one
+/// stage per part.
+pub trait Solution {
+    /// Day of the puzzle, from 1 to 25.
+    const DAY: u8;
+
+    /// Parsed form of the puzzle input.
+    type Input: 'static;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+
+    fn part1(input: &Self::Input) -> Result<Answer>;
+
+    fn part2(input: &Self::Input) -> Result<Answer>;
+}
+
+/// Type-erased view of a `Solution`, so that days with different input types
+/// can be listed side by side in the registry.
+pub trait Puzzle: Sync {
+    fn day(&self) -> u8;
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>>;
+
+    /// Solves one part from the output of `parse`.
+    fn solve(&self, input: &dyn Any, part: Part) -> Result<Answer>;
+
+    /// Parses the input and solves one part of it.
+    fn run(&self, input: &str, part: Part) -> Result<Answer> {
+        let parsed = self.parse(input)?;
+        self.solve(parsed.as_ref(), part)
+    }
+}
+
+impl<S: Solution + Sync> Puzzle for S {
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn solve(&self, input: &dyn Any, part: Part) -> Result<Answer> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .ok_or_else(|| Error::Unsolvable(format!("input was not parsed by day {}", S::DAY)))?;
+        match part {
+            Part::One => S::part1(input),
+            Part::Two => S::part2(input),
+        }
+    }
+}
+
+/// Every implemented day, in order.
+static REGISTRY: [&dyn Puzzle; 5] = [
+    &sonar::Day1,
+    &submarine::Day2,
+    &diagnostics::Day3,
+    &bingo::Day4,
+    &htv::Day5,
+];
+
+pub fn registry() -> &'static [&'static dyn Puzzle] {
+    &REGISTRY
+}
+
+/// Finds the solution of the given day, if it is implemented.
+pub fn find(day: u8) -> Option<&'static dyn Puzzle> {
+    REGISTRY.iter().copied().find(|p| p.day() == day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let days: Vec<u8> = registry().iter().map(|p| p.day()).collect();
+        assert_eq!(days, vec![1, 2, 3, 4, 5]);
+        assert!(find(6).is_none());
+    }
+
+    #[test]
+    fn test_run() {
+        let raw = "199
+200
+208
+210
+200
+207
+240
+269
+260
+263";
+        let day = find(1).unwrap();
+        assert_eq!(day.run(raw, Part::One).unwrap(), 7);
+        assert_eq!(day.run(raw, Part::Two).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_solve_wrong_input() {
+        let parsed = find(1).unwrap().parse("1\n2").unwrap();
+        assert!(find(2).unwrap().solve(parsed.as_ref(), Part::One).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+/// Sonar Sweep depth measurements
+///
+/// Depths are `i32` for the puzzle, but every function here works with any
+/// width, like `i64`, `u64` or `i128` for larger synthetic inputs.
+use std::{collections::VecDeque, io::BufRead, str::FromStr};
+
+use crate::error::{ParseError, Result};
+use crate::solution::{Answer, Solution};
+use crate::window::{Num, Sum, Windowed};
+
+pub fn str_to_num_vec<T: FromStr>(s: &str) -> Result<Vec<T>> {
+    let mut res: Vec<T> = vec![];
+    for (i, l) in s.lines().enumerate() {
+        res.push(parse_depth(l, i + 1)?);
+    }
+    Ok(res)
+}
+
+/// Parses the depth on the 1-based line `n`.
+fn parse_depth<T: FromStr>(l: &str, n: usize) -> Result<T> {
+    Ok(l.parse::<T>()
+        .map_err(|_| ParseError::new(l, l, "invalid depth").at_line(n))?)
+}
+
+/// Reads one depth per line from `r`, without loading the whole input in
+/// memory.
+pub fn read_depths<T: FromStr, R: BufRead>(r: R) -> impl Iterator<Item = Result<T>> {
+    r.lines().enumerate().map(|(i, l)| parse_depth(&l?, i + 1))
+}
+
+/// Counts the increases between consecutive windows of `window` depths of
+/// type `T` read from `r`, in a single pass.
+pub fn count_increases_from<T, R>(r: R, window: usize) -> Result<usize>
+where
+    T: FromStr + PartialOrd,
+    R: BufRead,
+{
+    let mut error = None;
+    let count = read_depths::<T, R>(r)
+        .map_while(|d| d.map_err(|e| error = Some(e)).ok())
+        .count_increases(window);
+    match error {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+/// Iterator adapter yielding, for each depth past the first `window` ones,
+/// whether the window ending on it has a larger sum than the previous window.
+///
+/// Both windows share all but their first and last depths, so comparing their
+/// sums comes down to comparing `a[i + window]` with `a[i]`, which only needs
+/// the last `window` depths in memory and can't overflow.
+pub struct SonarSweep<I: Iterator> {
+    inner: I,
+    window: usize,
+    buf: VecDeque<I::Item>,
+}
+
+impl<I> Iterator for SonarSweep<I>
+where
+    I: Iterator,
+    I::Item: PartialOrd,
+{
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        loop {
+            let depth = self.inner.next()?;
+            if self.buf.len() < self.window {
+                self.buf.push_back(depth);
+                continue;
+            }
+
+            // an empty window never increases.
+            let oldest = match self.buf.pop_front() {
+                Some(oldest) => oldest,
+                None => return Some(false),
+            };
+            let increase = depth > oldest;
+            self.buf.push_back(depth);
+            return Some(increase);
+        }
+    }
+}
+
+pub trait SonarSweepExt: Iterator + Sized
+where
+    Self::Item: PartialOrd,
+{
+    fn sonar_sweep(self, window: usize) -> SonarSweep<Self> {
+        SonarSweep {
+            inner: self,
+            window,
+            buf: VecDeque::with_capacity(window),
+        }
+    }
+
+    fn count_increases(self, window: usize) -> usize {
+        self.sonar_sweep(window)
+            .filter(|&increase| increase)
+            .count()
+    }
+}
+
+impl<I> SonarSweepExt for I
+where
+    I: Iterator,
+    I::Item: PartialOrd,
+{
+}
+
+/// Day 1 - part 1
+pub fn num_increase<T: PartialOrd>(data: Vec<T>) -> usize {
+    data.into_iter().count_increases(1)
+}
+
+/// Day 1 - part 2
+///
+/// Fails if the sum of a window overflows `T`.
+pub fn moving_window<T: Num>(data: Vec<T>, size: usize) -> Result<Vec<T>> {
+    Windowed::new(size, Sum).iter(&data).collect()
+}
+
+/// Counts the increases between the sums of consecutive windows of `size`
+/// depths, failing if a sum overflows `T`.
+pub fn sum_increases<T: Num>(depths: &[T], size: usize) -> Result<usize> {
+    let mut error = None;
+    let count = Windowed::new(size, Sum)
+        .iter(depths)
+        .map_while(|s| s.map_err(|e| error = Some(e)).ok())
+        .count_increases(1);
+    match error {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u8 = 1;
+    type Input = Vec<i32>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        str_to_num_vec(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        Ok(sum_increases(input, 1)? as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        Ok(sum_increases(input, 3)? as Answer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+
+    #[test]
+    fn test_num_increase() {
+        let data = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(num_increase(data), 7);
+    }
+
+    #[test]
+    fn test_moving_window() {
+        let data = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let sums = moving_window(data, 3).unwrap();
+        assert_eq!(sums, vec![607, 618, 618, 617, 647, 716, 769, 792]);
+        assert_eq!(num_increase(sums), 5);
+    }
+
+    #[test]
+    fn test_wide_depths() {
+        let raw = "9223372036854775000\n9223372036854775001\n9223372036854775002\n";
+        let data: Vec<i64> = str_to_num_vec(raw).unwrap();
+        assert_eq!(num_increase(data.clone()), 2);
+        match moving_window(data.clone(), 2).unwrap_err() {
+            crate::error::Error::Overflow(_) => (),
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert!(sum_increases(&data, 2).is_err());
+        // comparing windows without summing them can't overflow.
+        assert_eq!(
+            count_increases_from::<i64, _>(raw.as_bytes(), 2).unwrap(),
+            1
+        );
+
+        let data: Vec<i128> = data.into_iter().map(i128::from).collect();
+        assert_eq!(sum_increases(&data, 2).unwrap(), 1);
+        assert!(str_to_num_vec::<u64>("1\n-1\n").is_err());
+    }
+
+    #[test]
+    fn test_sonar_sweep() {
+        let data = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let sweep: Vec<bool> = data.iter().sonar_sweep(1).collect();
+        assert_eq!(sweep.len(), 9);
+        assert_eq!(sweep[..3], [true, true, true]);
+        assert_eq!(data.iter().count_increases(1), 7);
+
+        // same as comparing the sums of moving windows.
+        for size in 1..6 {
+            let sums = moving_window(data.clone(), size).unwrap();
+            assert_eq!(data.iter().count_increases(size), num_increase(sums));
+        }
+
+        assert_eq!(data.iter().count_increases(0), 0);
+        assert_eq!(data.iter().count_increases(20), 0);
+        assert_eq!([1.5, 0.5, 2.0].into_iter().count_increases(1), 1);
+    }
+
+    #[test]
+    fn test_count_increases_from() {
+        let raw = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+        assert_eq!(
+            count_increases_from::<i32, _>(raw.as_bytes(), 3).unwrap(),
+            5
+        );
+
+        let e = parse_err(count_increases_from::<u64, _>(
+            "199\n2x0\n208".as_bytes(),
+            1,
+        ));
+        assert_eq!(e.line, 2);
+    }
+}
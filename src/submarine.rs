@@ -1,53 +1,150 @@
 use std::str::FromStr;
 
-use anyhow::{anyhow, Result};
+use crate::envelope::Envelope;
+use crate::error::{parse_lines, Error, ParseError, Result};
+use crate::solution::{Answer, Solution};
+use crate::trace::{event, Level};
 
-#[derive(Clone, Debug)]
+/// Horizontal position and depth, the only coordinates of the puzzle. The
+/// `y` of mission files sits beside it in `Nav`, see `Nav::with_y`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Coord(pub i32, pub i32);
 
-#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Vector {
     dir: Direction,
     l: i32,
 }
 
 impl FromStr for Vector {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let mut parts = s.split(' ');
-        let dir = parts.next().expect("expected direction at first column");
-        let dir = Direction::from_str(dir)?;
-        let l = parts.next().expect("").parse::<i32>()?;
+        // the parts are slices of `s`, so that errors point at the right column.
+        let mut parts = s.split_whitespace();
+        let dir = parts.next().unwrap_or(&s[s.len()..]);
+        let dir = match dir {
+            "turn" => match parts.next() {
+                Some("left") => Direction::TurnLeft,
+                Some("right") => Direction::TurnRight,
+                side => {
+                    let side = side.unwrap_or(&s[s.len()..]);
+                    return Err(ParseError::new(s, side, "expected `left` or `right`").into());
+                }
+            },
+            dir => Direction::from_str(dir)
+                .map_err(|_| ParseError::new(s, dir, "unknown direction"))?,
+        };
+
+        let l = if dir == Direction::Surface {
+            0
+        } else {
+            let l = parts
+                .next()
+                .ok_or_else(|| ParseError::new(s, &s[s.len()..], "expected a length"))?;
+            let n = l
+                .parse::<i32>()
+                .map_err(|_| ParseError::new(s, l, "invalid length"))?;
+            match dir {
+                Direction::TurnLeft | Direction::TurnRight if n % 90 != 0 => {
+                    return Err(
+                        ParseError::new(s, l, "turns must be a multiple of 90 degrees").into(),
+                    );
+                }
+                Direction::Hold if n < 0 => {
+                    return Err(ParseError::new(s, l, "cannot hold for a negative time").into());
+                }
+                _ => n,
+            }
+        };
+        if let Some(extra) = parts.next() {
+            return Err(ParseError::new(s, extra, "unexpected token").into());
+        }
         Ok(Self { dir, l })
     }
 }
 
-#[derive(Debug)]
-enum Direction {
+impl Vector {
+    pub fn new(dir: Direction, l: i32) -> Self {
+        Self { dir, l }
+    }
+
+    pub fn dir(&self) -> Direction {
+        self.dir
+    }
+
+    /// Length of the move, or degrees of the turn, or steps of the hold.
+    pub fn length(&self) -> i32 {
+        self.l
+    }
+}
+
+/// Commands of the submarine. The puzzle only uses `forward`, `up` and
+/// `down`, the others are for mission files moving on the `x, y` plane.
+///
+/// Headings are restricted to the four axes of that plane, so that moves stay
+/// on the integer grid: turns only take multiples of 90 degrees, and other
+/// angles are parse errors rather than being rounded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Moves along the heading, diving along the aim in `NavMode::Aim`.
     Forward,
     Up,
     Down,
+    /// Exactly undoes a `forward` of the same length.
+    Back,
+    /// Moves to the right of the heading, or to the left for a negative
+    /// length, without changing depth.
+    Strafe,
+    /// Turns counterclockwise by a multiple of 90 degrees.
+    TurnLeft,
+    /// Turns clockwise by a multiple of 90 degrees.
+    TurnRight,
+    /// Goes back to depth 0, levelling the aim in `NavMode::Aim`. Takes no
+    /// length.
+    Surface,
+    /// Stays in place for the given number of steps.
+    Hold,
 }
 
 impl FromStr for Direction {
-    type Err = anyhow::Error;
+    type Err = Error;
 
+    /// Parses the single word commands, turns are parsed by `Vector`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "forward" => Ok(Direction::Forward),
             "up" => Ok(Direction::Up),
             "down" => Ok(Direction::Down),
-            _ => Err(anyhow!("unknown direction")),
+            "back" => Ok(Direction::Back),
+            "strafe" => Ok(Direction::Strafe),
+            "surface" => Ok(Direction::Surface),
+            "hold" => Ok(Direction::Hold),
+            _ => Err(ParseError::new(s, s, "unknown direction").into()),
         }
     }
 }
 
-#[allow(dead_code)]
+/// How `up` and `down` steer the submarine.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NavMode {
+    /// `up` and `down` change the depth directly, as in part 1.
+    Direct,
+    /// `up` and `down` change the aim, and `forward` dives along it, as in
+    /// part 2.
+    #[default]
+    Aim,
+}
+
+#[derive(Clone, Debug)]
 pub struct Nav {
     directions: Vec<Vector>,
     start: Coord,
+    y: i32,
+    heading: i32,
     aim: i32,
+    mode: NavMode,
+    envelope: Envelope,
 }
 
 impl Default for Nav {
@@ -55,42 +152,549 @@ impl Default for Nav {
         Self {
             directions: Default::default(),
             start: Coord(0, 0),
+            y: Default::default(),
+            heading: Default::default(),
             aim: Default::default(),
+            mode: Default::default(),
+            envelope: Default::default(),
         }
     }
 }
 
 impl From<Vec<Vector>> for Nav {
     fn from(v: Vec<Vector>) -> Self {
-        let mut m = Nav::default();
-        m.directions = v;
-        m
+        Self {
+            directions: v,
+            ..Default::default()
+        }
     }
 }
 
 impl Nav {
-    #[allow(dead_code)]
-    pub fn follow(mut self) -> EndState {
-        let mut coord = self.start.clone();
-        for v in self.directions {
-            match v.dir {
-                Direction::Forward => {
-                    coord.0 += v.l;
-                    coord.1 += v.l * self.aim;
+    pub fn new(directions: Vec<Vector>, start: Coord) -> Self {
+        Self {
+            directions,
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Same navigation with other commands.
+    pub fn with_directions(mut self, directions: Vec<Vector>) -> Self {
+        self.directions = directions;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: NavMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Position to the left of heading 0 at the start, 0 by default.
+    pub fn with_y(mut self, y: i32) -> Self {
+        self.y = y;
+        self
+    }
+
+    /// Heading at the start, 0 by default, in degrees counterclockwise and
+    /// rounded down to a multiple of 90 like turns.
+    pub fn with_heading(mut self, heading: i32) -> Self {
+        self.heading = heading.rem_euclid(360) / 90 * 90;
+        self
+    }
+
+    /// Aim at the start, 0 by default.
+    pub fn with_aim(mut self, aim: i32) -> Self {
+        self.aim = aim;
+        self
+    }
+
+    pub fn with_envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn directions(&self) -> &[Vector] {
+        &self.directions
+    }
+
+    pub fn mode(&self) -> NavMode {
+        self.mode
+    }
+
+    /// Iterates over the state of the submarine after each command.
+    pub fn steps(&self) -> Steps<'_> {
+        Steps {
+            nav: self,
+            next: 0,
+            aborted: false,
+            coord: self.start.clone(),
+            y: self.y,
+            heading: self.heading,
+            aim: self.aim,
+        }
+    }
+
+    /// State of the submarine after the first `n` commands, or after all of
+    /// them if there are fewer.
+    pub fn replay(&self, n: usize) -> EndState {
+        let mut steps = self.steps();
+        for _ in steps.by_ref().take(n) {}
+        steps.end_state()
+    }
+
+    /// Follows every command, stopping before the first one the envelope
+    /// aborts on or that overflows. Use `try_follow` to find out about it.
+    pub fn follow(self) -> EndState {
+        self.replay(self.directions.len())
+    }
+
+    /// Follows every command, failing on the first one the envelope aborts
+    /// on or that overflows.
+    pub fn try_follow(self) -> Result<EndState> {
+        let mut steps = self.steps();
+        while steps.try_next()?.is_some() {}
+        Ok(steps.end_state())
+    }
+}
+
+/// State of the submarine right after the command at `index`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub index: usize,
+    /// Position along heading 0.
+    pub horizontal: i32,
+    /// Position to the left of heading 0.
+    pub y: i32,
+    pub depth: i32,
+    /// Heading in degrees counterclockwise from heading 0, from 0
+    /// to 270.
+    pub heading: i32,
+    /// Always 0 in `NavMode::Direct`.
+    pub aim: i32,
+}
+
+/// Iterator over the steps of a `Nav`, see `Nav::steps`.
+#[derive(Clone, Debug)]
+pub struct Steps<'a> {
+    nav: &'a Nav,
+    /// Index of the next command to follow.
+    next: usize,
+    /// Whether the envelope aborted on a command.
+    aborted: bool,
+    coord: Coord,
+    y: i32,
+    heading: i32,
+    aim: i32,
+}
+
+impl Steps<'_> {
+    /// Moves to the state right after the command at `index`, going back to
+    /// the start if it was already followed. Returns `None`, leaving the
+    /// iterator exhausted, if there is no such command.
+    pub fn seek(&mut self, index: usize) -> Option<Step> {
+        if index < self.next {
+            *self = self.nav.steps();
+        }
+        self.nth(index - self.next)
+    }
+
+    /// Follows the next command, failing if the envelope aborts on it or the
+    /// state overflows. The iterator then stays on the state before that
+    /// command.
+    pub fn try_next(&mut self) -> Result<Option<Step>> {
+        if self.aborted {
+            return Ok(None);
+        }
+        let index = self.next;
+        let v = match self.nav.directions.get(index) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        self.next += 1;
+
+        let before = (self.coord.clone(), self.y, self.heading, self.aim);
+        let checked = self
+            .nav
+            .envelope
+            .check_command(index, v)
+            .and_then(|v| match v {
+                Some(v) => {
+                    self.apply(index, &v)?;
+                    self.nav
+                        .envelope
+                        .check_state(index, &mut self.coord.1, &mut self.aim)
                 }
-                Direction::Up => self.aim -= v.l,
-                Direction::Down => self.aim += v.l,
+                None => Ok(false),
+            });
+        match checked {
+            Ok(true) => (),
+            Ok(false) => (self.coord, self.y, self.heading, self.aim) = before,
+            Err(e) => {
+                (self.coord, self.y, self.heading, self.aim) = before;
+                self.aborted = true;
+                return Err(e);
             }
         }
 
+        Ok(Some(Step {
+            index,
+            horizontal: self.coord.0,
+            y: self.y,
+            depth: self.coord.1,
+            heading: self.heading,
+            aim: self.aim,
+        }))
+    }
+
+    /// Moves the submarine by `v`, regardless of the envelope. Fails without
+    /// moving if the state doesn't fit in `i32` afterwards.
+    fn apply(&mut self, index: usize, v: &Vector) -> Result<()> {
+        // unit vector of the heading, which is always a multiple of 90.
+        let (dx, dy) = match self.heading {
+            90 => (0, 1),
+            180 => (-1, 0),
+            270 => (0, -1),
+            _ => (1, 0),
+        };
+        // products of two `i32`s and their sums fit in `i64`.
+        let (mut x, mut y) = (i64::from(self.coord.0), i64::from(self.y));
+        let (mut depth, mut aim) = (i64::from(self.coord.1), i64::from(self.aim));
+        let mut heading = self.heading;
+        let l = i64::from(v.l);
+        let mut advance = |l: i64, aim: i64| {
+            x += l * dx;
+            y += l * dy;
+            depth += l * aim;
+        };
+        match (self.nav.mode, v.dir) {
+            (NavMode::Direct, Direction::Forward) => advance(l, 0),
+            (NavMode::Direct, Direction::Back) => advance(-l, 0),
+            (NavMode::Direct, Direction::Up) => depth -= l,
+            (NavMode::Direct, Direction::Down) => depth += l,
+            (NavMode::Aim, Direction::Forward) => advance(l, aim),
+            (NavMode::Aim, Direction::Back) => advance(-l, aim),
+            (NavMode::Aim, Direction::Up) => aim -= l,
+            (NavMode::Aim, Direction::Down) => aim += l,
+            (_, Direction::Strafe) => {
+                x += l * dy;
+                y -= l * dx;
+            }
+            (_, Direction::TurnLeft) => heading = (i64::from(heading) + l).rem_euclid(360) as i32,
+            (_, Direction::TurnRight) => heading = (i64::from(heading) - l).rem_euclid(360) as i32,
+            (_, Direction::Surface) => {
+                depth = 0;
+                aim = 0;
+            }
+            (_, Direction::Hold) => (),
+        }
+
+        let narrow = |n: i64, what: &str| {
+            i32::try_from(n).map_err(|_| {
+                Error::Overflow(format!(
+                    "command {}: {} {} doesn't fit in i32",
+                    index, what, n
+                ))
+            })
+        };
+        let state = (
+            Coord(narrow(x, "horizontal position")?, narrow(depth, "depth")?),
+            narrow(y, "y")?,
+            narrow(aim, "aim")?,
+        );
+        (self.coord, self.y, self.aim) = state;
+        self.heading = heading;
+        event!(
+            Level::Trace,
+            "{:?} {}: position {}, {}, depth {}, heading {}, aim {}",
+            v.dir,
+            v.l,
+            self.coord.0,
+            self.y,
+            self.coord.1,
+            self.heading,
+            self.aim
+        );
+        Ok(())
+    }
+
+    /// State after the commands followed so far.
+    pub fn end_state(&self) -> EndState {
         EndState {
-            end: coord,
+            end: self.coord.clone(),
+            y: self.y,
+            heading: self.heading,
             aim: self.aim,
+            mode: self.nav.mode,
         }
     }
 }
 
+impl Iterator for Steps<'_> {
+    type Item = Step;
+
+    /// Stops at the first command the envelope aborts on or that overflows.
+    fn next(&mut self) -> Option<Step> {
+        self.try_next().ok().flatten()
+    }
+
+    /// Exact unless the envelope aborts on one of the commands left.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = match self.aborted {
+            true => 0,
+            false => self.nav.directions.len().saturating_sub(self.next),
+        };
+        (left, Some(left))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EndState {
+    /// Position along heading 0, and depth.
     pub end: Coord,
+    /// Position to the left of heading 0.
+    pub y: i32,
+    /// Heading in degrees counterclockwise from heading 0.
+    pub heading: i32,
+    /// Always 0 in `NavMode::Direct`.
     pub aim: i32,
+    pub mode: NavMode,
+}
+
+impl EndState {
+    pub fn horizontal(&self) -> i32 {
+        self.end.0
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    pub fn depth(&self) -> i32 {
+        self.end.1
+    }
+
+    pub fn heading(&self) -> i32 {
+        self.heading
+    }
+
+    pub fn aim(&self) -> i32 {
+        self.aim
+    }
+
+    /// Horizontal position multiplied by the depth, the answer of the part
+    /// matching the mode.
+    pub fn product(&self) -> i64 {
+        i64::from(self.horizontal()) * i64::from(self.depth())
+    }
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u8 = 2;
+    type Input = Vec<Vector>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer> {
+        let end = Nav::from(input.clone())
+            .with_mode(NavMode::Direct)
+            .try_follow()?;
+        Ok(end.product())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer> {
+        let end = Nav::from(input.clone())
+            .with_mode(NavMode::Aim)
+            .try_follow()?;
+        Ok(end.product())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+
+    const EXAMPLE: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+    #[test]
+    fn test_nav_modes() {
+        let input = Day2::parse(EXAMPLE).unwrap();
+
+        let end = Nav::from(input.clone()).with_mode(NavMode::Direct).follow();
+        assert_eq!((end.horizontal(), end.depth(), end.aim()), (15, 10, 0));
+        assert_eq!(end.product(), 150);
+
+        let end = Nav::from(input.clone()).follow();
+        assert_eq!(end.mode, NavMode::Aim);
+        assert_eq!((end.horizontal(), end.depth(), end.aim()), (15, 60, 10));
+        assert_eq!(end.product(), 900);
+
+        let end = Nav::new(input, Coord(1, 2))
+            .with_mode(NavMode::Direct)
+            .follow();
+        assert_eq!((end.horizontal(), end.depth()), (16, 12));
+    }
+
+    #[test]
+    fn test_steps() {
+        let nav = Nav::from(Day2::parse(EXAMPLE).unwrap());
+        let steps: Vec<Step> = nav.steps().collect();
+        assert_eq!(steps.len(), 6);
+        assert_eq!(
+            steps[2],
+            Step {
+                index: 2,
+                horizontal: 13,
+                y: 0,
+                depth: 40,
+                heading: 0,
+                aim: 5
+            }
+        );
+        assert_eq!(
+            steps.last().map(|s| s.depth),
+            Some(nav.clone().follow().depth())
+        );
+
+        let mut it = nav.steps();
+        assert_eq!(it.seek(4), Some(steps[4].clone()));
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.seek(1), Some(steps[1].clone()));
+        assert_eq!(it.next(), Some(steps[2].clone()));
+        assert_eq!(it.end_state().depth(), 40);
+        assert_eq!(it.seek(6), None);
+
+        let prefix = nav.replay(3);
+        assert_eq!(
+            (prefix.horizontal(), prefix.depth(), prefix.aim()),
+            (13, 40, 5)
+        );
+        assert_eq!(nav.replay(0).depth(), 0);
+        assert_eq!(nav.replay(100).product(), 900);
+    }
+
+    #[test]
+    fn test_extended_commands() {
+        let raw = "forward 5
+down 2
+turn left 90
+forward 3
+strafe 2
+back 1
+turn right 180
+hold 4
+forward 1
+surface";
+        let nav = Nav::from(Day2::parse(raw).unwrap());
+        let steps: Vec<Step> = nav.steps().collect();
+        assert_eq!((steps[3].horizontal, steps[3].y, steps[3].depth), (5, 3, 6));
+        // to the right of a northward heading is east.
+        assert_eq!((steps[4].horizontal, steps[4].y), (7, 3));
+        assert_eq!((steps[5].horizontal, steps[5].y, steps[5].depth), (7, 2, 4));
+        assert_eq!(steps[6].heading, 270);
+        assert_eq!(
+            steps[7],
+            Step {
+                index: 7,
+                ..steps[6].clone()
+            }
+        );
+        assert_eq!((steps[8].y, steps[8].depth), (1, 6));
+
+        let end = nav.clone().follow();
+        assert_eq!(
+            (end.horizontal(), end.y(), end.depth(), end.aim()),
+            (7, 1, 0, 0)
+        );
+        assert_eq!(end.heading(), 270);
+
+        let end = Nav::from(Day2::parse(raw).unwrap())
+            .with_mode(NavMode::Direct)
+            .replay(6);
+        assert_eq!((end.horizontal(), end.y(), end.depth()), (7, 2, 2));
+
+        let end = Nav::new(Day2::parse("forward 2\nstrafe 1").unwrap(), Coord(0, 4))
+            .with_y(3)
+            .with_heading(-270)
+            .follow();
+        assert_eq!((end.horizontal(), end.y(), end.heading()), (1, 5, 90));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let nav = Nav::from(Day2::parse("forward 2147483647\nforward 1").unwrap());
+        match nav.clone().try_follow().unwrap_err() {
+            Error::Overflow(reason) => assert_eq!(
+                reason,
+                "command 1: horizontal position 2147483648 doesn't fit in i32"
+            ),
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert_eq!(nav.follow().horizontal(), i32::MAX);
+
+        let nav = Nav::from(Day2::parse("down 2\nforward 1073741824").unwrap());
+        assert!(nav.try_follow().is_err());
+
+        // the answers fail rather than stopping short.
+        let input = Day2::parse("down 1\nforward 2147483647\nforward 5").unwrap();
+        assert!(matches!(Day2::part1(&input), Err(Error::Overflow(_))));
+        assert!(matches!(Day2::part2(&input), Err(Error::Overflow(_))));
+    }
+
+    #[test]
+    fn test_extended_errors() {
+        for (raw, column, reason) in [
+            ("turn 90", 6, "expected `left` or `right`"),
+            ("turn left", 10, "expected a length"),
+            (
+                "turn right 45",
+                12,
+                "turns must be a multiple of 90 degrees",
+            ),
+            ("hold -1", 6, "cannot hold for a negative time"),
+            ("surface 3", 9, "unexpected token"),
+        ] {
+            let e = parse_err(Vector::from_str(raw));
+            assert_eq!((e.column, e.reason.as_str()), (column, reason));
+        }
+    }
+
+    #[test]
+    fn test_vector_errors() {
+        let e = parse_err(Vector::from_str("sideways 3"));
+        assert_eq!(e.column, 1);
+        assert_eq!(e.token, "sideways");
+
+        assert_eq!(parse_err(Vector::from_str("forward")).column, 8);
+
+        // whitespace around and between the tokens doesn't matter.
+        assert_eq!(
+            Vector::from_str("forward 5 ").unwrap(),
+            Vector::new(Direction::Forward, 5)
+        );
+        assert_eq!(
+            Vector::from_str(" turn  left\t90").unwrap(),
+            Vector::new(Direction::TurnLeft, 90)
+        );
+        let e = parse_err(Vector::from_str("  down  x3 "));
+        assert_eq!((e.column, e.token.as_str()), (9, "x3"));
+
+        let raw = "forward 5
+down 5
+forward x8";
+        let e = parse_err(Day2::parse(raw));
+        assert_eq!(e.line, 3);
+        assert_eq!(e.column, 9);
+        assert_eq!(e.token, "x8");
+    }
 }
@@ -0,0 +1,440 @@
+/// Submission of answers to the puzzle website
+use std::{
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::{Error, ParseError, Result};
+use crate::fetch::{http_error, write_atomic, Connection};
+use crate::solution::{Answer, Part};
+
+/// How long to wait after a wrong answer before submitting again.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Verdict of the puzzle website on a submitted answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    Wrong,
+    TooHigh,
+    TooLow,
+    /// An answer was submitted too recently, with the time left to wait.
+    Wait(Duration),
+    /// The part was already solved, or isn't unlocked yet.
+    WrongLevel,
+    /// The response couldn't be understood.
+    Unknown,
+}
+
+impl Outcome {
+    /// Reads the verdict out of the response page.
+    pub fn from_response(body: &str) -> Self {
+        if body.contains("That's the right answer") {
+            Outcome::Correct
+        } else if body.contains("your answer is too high") {
+            Outcome::TooHigh
+        } else if body.contains("your answer is too low") {
+            Outcome::TooLow
+        } else if body.contains("That's not the right answer") {
+            Outcome::Wrong
+        } else if body.contains("You gave an answer too recently") {
+            Outcome::Wait(parse_wait(body).unwrap_or(DEFAULT_COOLDOWN))
+        } else if body.contains("You don't seem to be solving the right level") {
+            Outcome::WrongLevel
+        } else {
+            Outcome::Unknown
+        }
+    }
+
+    pub fn is_wrong(&self) -> bool {
+        matches!(self, Outcome::Wrong | Outcome::TooHigh | Outcome::TooLow)
+    }
+}
+
+/// Reads the time left from "You have 1m 20s left to wait".
+fn parse_wait(body: &str) -> Option<Duration> {
+    let start = body.find("You have ")? + "You have ".len();
+    let end = start + body[start..].find(" left to wait")?;
+    let mut secs = 0;
+    for part in body[start..end].split_whitespace() {
+        let (n, unit) = part.split_at(part.len().checked_sub(1)?);
+        let n = n.parse::<u64>().ok()?;
+        secs += match unit {
+            "h" => n * 3600,
+            "m" => n * 60,
+            "s" => n,
+            _ => return None,
+        };
+    }
+    Some(Duration::from_secs(secs))
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Correct => write!(f, "correct"),
+            Outcome::Wrong => write!(f, "wrong"),
+            Outcome::TooHigh => write!(f, "too-high"),
+            Outcome::TooLow => write!(f, "too-low"),
+            Outcome::Wait(d) => write!(f, "wait:{}", d.as_secs()),
+            Outcome::WrongLevel => write!(f, "wrong-level"),
+            Outcome::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "correct" => Outcome::Correct,
+            "wrong" => Outcome::Wrong,
+            "too-high" => Outcome::TooHigh,
+            "too-low" => Outcome::TooLow,
+            "wrong-level" => Outcome::WrongLevel,
+            "unknown" => Outcome::Unknown,
+            s => {
+                let secs = s
+                    .strip_prefix("wait:")
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .ok_or_else(|| ParseError::new(s, s, "unknown outcome"))?;
+                Outcome::Wait(Duration::from_secs(secs))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub day: u8,
+    pub part: Part,
+    pub answer: Answer,
+    pub outcome: Outcome,
+}
+
+/// Every answer submitted so far, one tab separated attempt per line:
+/// `<time> <day> <part> <answer> <outcome>`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct History {
+    pub attempts: Vec<Attempt>,
+}
+
+impl History {
+    /// Returns the reason why `answer` shouldn't be submitted at time `now`,
+    /// if any.
+    pub fn refusal(
+        &self,
+        day: u8,
+        part: Part,
+        answer: Answer,
+        now: u64,
+        cooldown: Duration,
+    ) -> Option<String> {
+        if let Some(last) = self.attempts.last() {
+            let wait = match last.outcome {
+                Outcome::Wait(d) => Some(d),
+                o if o.is_wrong() => Some(cooldown),
+                _ => None,
+            };
+            if let Some(until) = wait.map(|d| last.time + d.as_secs()) {
+                if now < until {
+                    return Some(format!("cooling down for another {}s", until - now));
+                }
+            }
+        }
+
+        for a in self
+            .attempts
+            .iter()
+            .filter(|a| a.day == day && a.part == part)
+        {
+            match a.outcome {
+                Outcome::Correct => {
+                    return Some(format!("day {} part {} is already solved", day, part))
+                }
+                o if o.is_wrong() && a.answer == answer => {
+                    return Some(format!("{} is known to be {}", answer, o))
+                }
+                Outcome::TooHigh if answer >= a.answer => {
+                    return Some(format!("{} is too high, {} already was", answer, a.answer))
+                }
+                Outcome::TooLow if answer <= a.answer => {
+                    return Some(format!("{} is too low, {} already was", answer, a.answer))
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+impl FromStr for History {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut attempts = vec![];
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 5 {
+                let reason = format!("expected 5 fields, found {}", fields.len());
+                return Err(ParseError::new(line, line, reason).at_line(i + 1).into());
+            }
+            let invalid = |token: &str, what: &str| {
+                ParseError::new(line, token, format!("invalid {}", what)).at_line(i + 1)
+            };
+
+            attempts.push(Attempt {
+                time: fields[0].parse().map_err(|_| invalid(fields[0], "time"))?,
+                day: fields[1].parse().map_err(|_| invalid(fields[1], "day"))?,
+                part: fields[2]
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|n| Part::try_from(n).ok())
+                    .ok_or_else(|| invalid(fields[2], "part"))?,
+                answer: fields[3]
+                    .parse()
+                    .map_err(|_| invalid(fields[3], "answer"))?,
+                outcome: fields[4]
+                    .parse()
+                    .map_err(|_| invalid(fields[4], "outcome"))?,
+            });
+        }
+        Ok(Self { attempts })
+    }
+}
+
+impl Display for History {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for a in &self.attempts {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}",
+                a.time, a.day, a.part, a.answer, a.outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Posts answers to the puzzle website, keeping track of every attempt in a
+/// local history file.
+#[derive(Debug, Clone)]
+pub struct Submitter {
+    connection: Connection,
+    history: PathBuf,
+    cooldown: Duration,
+}
+
+impl Submitter {
+    pub fn new(history: impl Into<PathBuf>) -> Self {
+        Self {
+            connection: Connection::default(),
+            history: history.into(),
+            cooldown: DEFAULT_COOLDOWN,
+        }
+    }
+
+    pub fn with_connection(mut self, connection: Connection) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    /// Sets how long to wait after a wrong answer.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn history(&self) -> Result<History> {
+        match fs::read_to_string(&self.history) {
+            Ok(s) => History::from_str(&s),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(History::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn submit(&self, day: u8, part: Part, answer: Answer) -> Result<Outcome> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.submit_at(day, part, answer, now)
+    }
+
+    /// Submits `answer` as if the current time was `now`, in seconds since
+    /// the Unix epoch.
+    pub fn submit_at(&self, day: u8, part: Part, answer: Answer, now: u64) -> Result<Outcome> {
+        let mut history = self.history()?;
+        if let Some(reason) = history.refusal(day, part, answer, now, self.cooldown) {
+            return Err(Error::Refused(reason));
+        }
+
+        let req = self
+            .connection
+            .request("POST", day, "answer", "submit answers")?;
+        let url = req.url().to_string();
+        let resp = req
+            .send_form(&[
+                ("level", &part.to_string()),
+                ("answer", &answer.to_string()),
+            ])
+            .map_err(|e| http_error(&url, e))?;
+        let outcome = Outcome::from_response(&resp.into_string()?);
+
+        history.attempts.push(Attempt {
+            time: now,
+            day,
+            part,
+            answer,
+            outcome,
+        });
+        write_atomic(&self.history, &history.to_string())?;
+
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+    use crate::mock::MockServer;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("aoc2021-{}-{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_outcome_from_response() {
+        let page = |s: &str| format!("<main><article><p>{}</p></article></main>", s);
+        assert_eq!(
+            Outcome::from_response(&page(
+                "That's the right answer! You are one gold star closer."
+            )),
+            Outcome::Correct
+        );
+        assert_eq!(
+            Outcome::from_response(&page(
+                "That's not the right answer; your answer is too high."
+            )),
+            Outcome::TooHigh
+        );
+        assert_eq!(
+            Outcome::from_response(&page(
+                "That's not the right answer; your answer is too low."
+            )),
+            Outcome::TooLow
+        );
+        assert_eq!(
+            Outcome::from_response(&page("That's not the right answer.")),
+            Outcome::Wrong
+        );
+        assert_eq!(
+            Outcome::from_response(&page(
+                "You gave an answer too recently. You have 1m 20s left to wait."
+            )),
+            Outcome::Wait(Duration::from_secs(80))
+        );
+        assert_eq!(
+            Outcome::from_response(&page(
+                "You don't seem to be solving the right level. Did you already complete it?"
+            )),
+            Outcome::WrongLevel
+        );
+        assert_eq!(Outcome::from_response("<html></html>"), Outcome::Unknown);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let raw = "1000\t1\t1\t42\ttoo-high
+1100\t1\t1\t7\twait:34
+1200\t1\t1\t7\tcorrect
+";
+        let history = History::from_str(raw).unwrap();
+        assert_eq!(history.attempts.len(), 3);
+        assert_eq!(
+            history.attempts[1].outcome,
+            Outcome::Wait(Duration::from_secs(34))
+        );
+        assert_eq!(history.to_string(), raw);
+
+        assert_eq!(
+            parse_err(History::from_str("1000\t1\t3\t42\twrong")).column,
+            8
+        );
+    }
+
+    #[test]
+    fn test_refusal() {
+        let cooldown = Duration::from_secs(60);
+        let history = History::from_str("1000\t1\t1\t42\ttoo-high\n").unwrap();
+        assert!(history.refusal(1, Part::One, 10, 1030, cooldown).is_some());
+        assert!(history.refusal(1, Part::One, 10, 1060, cooldown).is_none());
+        assert!(history.refusal(1, Part::One, 42, 2000, cooldown).is_some());
+        assert!(history.refusal(1, Part::One, 50, 2000, cooldown).is_some());
+        assert!(history.refusal(1, Part::Two, 50, 2000, cooldown).is_none());
+
+        let history = History::from_str("1000\t1\t1\t42\tcorrect\n").unwrap();
+        assert!(history.refusal(1, Part::One, 43, 2000, cooldown).is_some());
+        assert!(history.refusal(1, Part::Two, 42, 1001, cooldown).is_none());
+    }
+
+    #[test]
+    fn test_submit() {
+        let server = MockServer::start(|req| {
+            if req.body.contains("answer=7") {
+                (200, "<p>That's the right answer!</p>".to_string())
+            } else {
+                (200, "<p>That's not the right answer.</p>".to_string())
+            }
+        });
+        let path = temp_file("submit");
+        let submitter = Submitter::new(&path).with_connection(
+            Connection::default()
+                .with_base_url(&server.url)
+                .with_session("abc"),
+        );
+
+        assert_eq!(
+            submitter.submit_at(1, Part::Two, 5, 1000).unwrap(),
+            Outcome::Wrong
+        );
+        assert!(matches!(
+            submitter.submit_at(1, Part::Two, 7, 1010),
+            Err(Error::Refused(_))
+        ));
+        assert!(matches!(
+            submitter.submit_at(1, Part::Two, 5, 2000),
+            Err(Error::Refused(_))
+        ));
+        assert_eq!(
+            submitter.submit_at(1, Part::Two, 7, 2000).unwrap(),
+            Outcome::Correct
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/2021/day/1/answer");
+        assert_eq!(requests[0].body, "level=2&answer=5");
+        assert_eq!(requests[0].header("Cookie"), Some("session=abc"));
+
+        let history = submitter.history().unwrap();
+        assert_eq!(history.attempts.len(), 2);
+        assert_eq!(history.attempts[1].outcome, Outcome::Correct);
+
+        let _ = fs::remove_file(&path);
+    }
+}
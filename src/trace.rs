@@ -0,0 +1,286 @@
+/// Opt-in step-by-step traces of the solutions
+///
+/// Nothing is recorded until a sink is installed, either for the whole process
+/// with `set_global` or for the current thread with `capture`. Events are
+/// filtered by level and module, with filters like `info,diagnostics=trace`.
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use crate::error::{Error, ParseError, Result};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        })
+    }
+}
+
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            s => Err(ParseError::new(s, s, "unknown level").into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub level: Level,
+    /// Module the event was emitted from, e.g. `aoc2021::diagnostics`.
+    pub module: &'static str,
+    pub message: String,
+}
+
+pub trait Sink: Send + Sync {
+    fn record(&self, event: &Event);
+}
+
+/// Writes every event to stderr.
+#[derive(Debug, Default)]
+pub struct StderrSink;
+
+impl Sink for StderrSink {
+    fn record(&self, event: &Event) {
+        eprintln!("[{:<5} {}] {}", event.level, event.module, event.message);
+    }
+}
+
+/// Keeps every event in memory, mostly for tests.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<Event>>,
+}
+
+impl MemorySink {
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl Sink for MemorySink {
+    fn record(&self, event: &Event) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+/// Maximum level of the events to record, per module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Level of the modules without a directive of their own.
+    default: Option<Level>,
+    directives: Vec<(String, Level)>,
+}
+
+impl Filter {
+    /// Records every event up to `level`, from any module.
+    pub fn new(level: Level) -> Self {
+        Self {
+            default: Some(level),
+            directives: vec![],
+        }
+    }
+
+    /// Records the events of `module` up to `level`. `module` is either a full
+    /// path like `aoc2021::htv` or the last segment of it like `htv`.
+    pub fn with_module(mut self, module: impl Into<String>, level: Level) -> Self {
+        self.directives.push((module.into(), level));
+        self
+    }
+
+    pub fn enabled(&self, level: Level, module: &str) -> bool {
+        let max = self
+            .directives
+            .iter()
+            .filter(|(name, _)| {
+                module == name
+                    || module.starts_with(&format!("{}::", name))
+                    || module.rsplit("::").next() == Some(name.as_str())
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, level)| *level)
+            .or(self.default);
+        matches!(max, Some(max) if level <= max)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    /// Parses comma separated directives, each either a level or a
+    /// `module=level` pair.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Filter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let level_of =
+                |l: &str| Level::from_str(l).map_err(|_| ParseError::new(s, l, "unknown level"));
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    filter = filter.with_module(module.trim(), level_of(level.trim())?);
+                }
+                None => filter.default = Some(level_of(directive)?),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[derive(Clone)]
+struct Subscriber {
+    sink: Arc<dyn Sink>,
+    filter: Arc<Filter>,
+}
+
+static GLOBAL: RwLock<Option<Subscriber>> = RwLock::new(None);
+
+thread_local! {
+    static LOCAL: RefCell<Option<Subscriber>> = const { RefCell::new(None) };
+}
+
+/// Sends the events of every thread matching `filter` to `sink`.
+pub fn set_global(sink: impl Sink + 'static, filter: Filter) {
+    *GLOBAL.write().unwrap() = Some(Subscriber {
+        sink: Arc::new(sink),
+        filter: Arc::new(filter),
+    });
+}
+
+pub fn clear_global() {
+    *GLOBAL.write().unwrap() = None;
+}
+
+/// Runs `f`, recording the events it emits on the current thread that match
+/// `filter`, instead of sending them to the global sink.
+pub fn capture<R>(filter: Filter, f: impl FnOnce() -> R) -> (R, Vec<Event>) {
+    let sink = Arc::new(MemorySink::default());
+    let subscriber = Subscriber {
+        sink: sink.clone(),
+        filter: Arc::new(filter),
+    };
+
+    let previous = LOCAL.with(|l| l.replace(Some(subscriber)));
+    let res = {
+        // restores the previous subscriber even if `f` panics.
+        let _restore = Restore(previous);
+        f()
+    };
+
+    (res, sink.events())
+}
+
+/// Puts back a subscriber of the current thread when dropped.
+struct Restore(Option<Subscriber>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        LOCAL.with(|l| *l.borrow_mut() = previous);
+    }
+}
+
+fn subscriber() -> Option<Subscriber> {
+    LOCAL
+        .with(|l| l.borrow().clone())
+        .or_else(|| GLOBAL.read().ok()?.clone())
+}
+
+/// Records an event, only building its message if a sink wants it. Use the
+/// `event!` macro rather than calling this directly.
+pub fn emit(level: Level, module: &'static str, message: impl FnOnce() -> String) {
+    if let Some(s) = subscriber() {
+        if s.filter.enabled(level, module) {
+            s.sink.record(&Event {
+                level,
+                module,
+                message: message(),
+            });
+        }
+    }
+}
+
+/// Emits a trace event with a `format!` style message.
+macro_rules! event {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::trace::emit($level, module_path!(), || format!($($arg)+))
+    };
+}
+
+pub(crate) use event;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::from_str("info, htv=trace,aoc2021::bingo=error").unwrap();
+        assert!(filter.enabled(Level::Info, "aoc2021::diagnostics"));
+        assert!(!filter.enabled(Level::Debug, "aoc2021::diagnostics"));
+        assert!(filter.enabled(Level::Trace, "aoc2021::htv"));
+        assert!(!filter.enabled(Level::Warn, "aoc2021::bingo"));
+        assert!(filter.enabled(Level::Error, "aoc2021::bingo"));
+
+        let filter = Filter::from_str("htv=debug").unwrap();
+        assert!(!filter.enabled(Level::Error, "aoc2021::bingo"));
+
+        assert_eq!(parse_err(Filter::from_str("info,htv=loud")).column, 10);
+    }
+
+    #[test]
+    fn test_capture() {
+        let ((), events) = capture(Filter::new(Level::Debug), || {
+            event!(Level::Debug, "hello {}", 1);
+            event!(Level::Trace, "too verbose");
+        });
+        assert_eq!(
+            events,
+            vec![Event {
+                level: Level::Debug,
+                module: "aoc2021::trace::test",
+                message: "hello 1".to_string(),
+            }]
+        );
+
+        // nothing is recorded outside of `capture`.
+        let ((), events) = capture(Filter::new(Level::Trace), || {});
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_capture_panic() {
+        let ((), events) = capture(Filter::new(Level::Info), || {
+            let res = std::panic::catch_unwind(|| {
+                capture(Filter::new(Level::Info), || panic!("lost"));
+            });
+            assert!(res.is_err());
+            event!(Level::Info, "after the panic");
+        });
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "after the panic");
+    }
+}
@@ -0,0 +1,200 @@
+/// Regression checks of the solutions against recorded answers
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::error::{Error, ParseError, Result};
+use crate::solution::{Answer, Part, Puzzle};
+
+/// Known good answers, keyed by day and part.
+///
+/// The recorded form has one answer per line, as `<day> <part> <answer>`.
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Answers {
+    inner: BTreeMap<(u8, Part), Answer>,
+}
+
+impl Answers {
+    pub fn get(&self, day: u8, part: Part) -> Option<Answer> {
+        self.inner.get(&(day, part)).copied()
+    }
+
+    pub fn insert(&mut self, day: u8, part: Part, answer: Answer) {
+        self.inner.insert((day, part), answer);
+    }
+}
+
+impl FromStr for Answers {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut answers = Answers::default();
+        for (i, line) in s.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = trimmed.split_whitespace();
+            let mut next = |name: &str| {
+                fields.next().ok_or_else(|| {
+                    ParseError::new(line, &line[line.len()..], format!("expected {}", name))
+                        .at_line(i + 1)
+                })
+            };
+            let (day, part, answer) = (next("a day")?, next("a part")?, next("an answer")?);
+
+            let day = day
+                .parse::<u8>()
+                .map_err(|_| ParseError::new(line, day, "invalid day").at_line(i + 1))?;
+            let part = part
+                .parse::<u8>()
+                .ok()
+                .and_then(|n| Part::try_from(n).ok())
+                .ok_or_else(|| ParseError::new(line, part, "invalid part").at_line(i + 1))?;
+            let answer = answer
+                .parse::<Answer>()
+                .map_err(|_| ParseError::new(line, answer, "invalid answer").at_line(i + 1))?;
+            answers.insert(day, part, answer);
+        }
+        Ok(answers)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail {
+        actual: Answer,
+        expected: Answer,
+    },
+    /// The solution returned an error.
+    Error(String),
+    /// There is no recorded answer or no input to check against.
+    Missing,
+}
+
+impl Status {
+    /// Returns true if the check found a regression.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Status::Fail { .. } | Status::Error(_))
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail { actual, expected } => {
+                write!(f, "FAIL got {}, expected {}", actual, expected)
+            }
+            Status::Error(e) => write!(f, "ERROR {}", e.lines().next().unwrap_or_default()),
+            Status::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub day: u8,
+    pub part: Part,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+/// Runs both parts of `puzzle` on `input` and compares them with the recorded
+/// answers. A missing input marks both parts as missing.
+pub fn check(puzzle: &dyn Puzzle, input: Option<&str>, answers: &Answers) -> Vec<Check> {
+    [Part::One, Part::Two]
+        .into_iter()
+        .map(|part| {
+            let day = puzzle.day();
+            let (input, expected) = match (input, answers.get(day, part)) {
+                (Some(input), Some(expected)) => (input, expected),
+                _ => {
+                    return Check {
+                        day,
+                        part,
+                        status: Status::Missing,
+                        elapsed: Duration::ZERO,
+                    }
+                }
+            };
+
+            let start = Instant::now();
+            let result = puzzle.run(input, part);
+            let elapsed = start.elapsed();
+
+            let status = match result {
+                Ok(actual) if actual == expected => Status::Pass,
+                Ok(actual) => Status::Fail { actual, expected },
+                Err(e) => Status::Error(e.to_string()),
+            };
+            Check {
+                day,
+                part,
+                status,
+                elapsed,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::parse_err;
+    use crate::solution::find;
+
+    #[test]
+    fn test_answers_from_str() {
+        let raw = "# day part answer
+1 1 7
+
+1 2   5";
+        let answers = Answers::from_str(raw).unwrap();
+        assert_eq!(answers.get(1, Part::One), Some(7));
+        assert_eq!(answers.get(1, Part::Two), Some(5));
+        assert_eq!(answers.get(2, Part::One), None);
+
+        assert_eq!(parse_err(Answers::from_str("1 3 7")).column, 3);
+    }
+
+    #[test]
+    fn test_check() {
+        let raw = "199
+200
+208
+210
+200
+207
+240
+269
+260
+263";
+        let mut answers = Answers::default();
+        answers.insert(1, Part::One, 7);
+        answers.insert(1, Part::Two, 6);
+
+        let checks = check(find(1).unwrap(), Some(raw), &answers);
+        assert_eq!(checks[0].status, Status::Pass);
+        assert_eq!(
+            checks[1].status,
+            Status::Fail {
+                actual: 5,
+                expected: 6
+            }
+        );
+        assert!(checks[1].status.is_failure());
+
+        let checks = check(find(1).unwrap(), None, &answers);
+        assert!(checks.iter().all(|c| c.status == Status::Missing));
+
+        let checks = check(find(2).unwrap(), Some(raw), &answers);
+        assert!(checks.iter().all(|c| c.status == Status::Missing));
+    }
+}
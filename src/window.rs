@@ -0,0 +1,275 @@
+/// Statistics over sliding windows of depth readings
+use std::{any::type_name, cmp::Ordering};
+
+use crate::error::{Error, Result};
+
+/// Numeric types that readings can be made of.
+pub trait Num: Copy + PartialOrd + std::ops::Add<Output = Self> {
+    const ZERO: Self;
+
+    fn to_f64(self) -> f64;
+
+    /// Nearest value to `v`, rounded for integers and saturating at the
+    /// bounds of `Self`.
+    fn from_f64(v: f64) -> Self;
+
+    /// `None` if the sum doesn't fit in `Self`, or isn't finite for floats.
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_num {
+    (int => $($t:ty),*) => {
+        $(impl Num for $t {
+            const ZERO: Self = 0;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(v: f64) -> Self {
+                v.round() as $t
+            }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+        })*
+    };
+    (float => $($t:ty),*) => {
+        $(impl Num for $t {
+            const ZERO: Self = 0.0;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                Some(self + rhs).filter(|s| s.is_finite())
+            }
+        })*
+    };
+}
+
+impl_num!(int => i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_num!(float => f32, f64);
+
+/// Summarizes one window of readings into a single value.
+pub trait Reducer<T> {
+    type Output;
+
+    /// `window` is never empty.
+    fn reduce(&self, window: &[T]) -> Self::Output;
+}
+
+/// Sum of the window, failing rather than wrapping if it overflows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sum;
+
+impl<T: Num> Reducer<T> for Sum {
+    type Output = Result<T>;
+
+    fn reduce(&self, window: &[T]) -> Result<T> {
+        window
+            .iter()
+            .try_fold(T::ZERO, |sum, &x| sum.checked_add(x))
+            .ok_or_else(|| {
+                Error::Overflow(format!(
+                    "sum of {} readings doesn't fit in {}",
+                    window.len(),
+                    type_name::<T>()
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Mean;
+
+impl<T: Num> Reducer<T> for Mean {
+    type Output = f64;
+
+    fn reduce(&self, window: &[T]) -> f64 {
+        window.iter().map(|x| x.to_f64()).sum::<f64>() / window.len() as f64
+    }
+}
+
+/// Middle reading of the window, or the mean of the two middle ones for
+/// windows of an even size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Median;
+
+impl<T: Num> Reducer<T> for Median {
+    type Output = f64;
+
+    fn reduce(&self, window: &[T]) -> f64 {
+        let mut sorted: Vec<f64> = window.iter().map(|x| x.to_f64()).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mid = sorted.len() / 2;
+        if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Min;
+
+impl<T: Num> Reducer<T> for Min {
+    type Output = T;
+
+    fn reduce(&self, window: &[T]) -> T {
+        window
+            .iter()
+            .copied()
+            .reduce(|a, b| if b < a { b } else { a })
+            .unwrap_or(T::ZERO)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Max;
+
+impl<T: Num> Reducer<T> for Max {
+    type Output = T;
+
+    fn reduce(&self, window: &[T]) -> T {
+        window
+            .iter()
+            .copied()
+            .reduce(|a, b| if b > a { b } else { a })
+            .unwrap_or(T::ZERO)
+    }
+}
+
+/// Population standard deviation of the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StdDev;
+
+impl<T: Num> Reducer<T> for StdDev {
+    type Output = f64;
+
+    fn reduce(&self, window: &[T]) -> f64 {
+        let mean = Mean.reduce(window);
+        let var = window
+            .iter()
+            .map(|x| (x.to_f64() - mean).powi(2))
+            .sum::<f64>()
+            / window.len() as f64;
+        var.sqrt()
+    }
+}
+
+/// Applies a reducer to every window of `size` readings, moving the window
+/// `stride` readings at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Windowed<R> {
+    size: usize,
+    stride: usize,
+    reducer: R,
+}
+
+impl<R> Windowed<R> {
+    /// A window `size` of 0 is treated as 1.
+    pub fn new(size: usize, reducer: R) -> Self {
+        Self {
+            size: size.max(1),
+            stride: 1,
+            reducer,
+        }
+    }
+
+    /// A `stride` of 0 is treated as 1.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride.max(1);
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn reducer(&self) -> &R {
+        &self.reducer
+    }
+
+    /// Reduces the windows of `data` lazily, in order.
+    pub fn iter<'a, T>(&'a self, data: &'a [T]) -> impl Iterator<Item = R::Output> + 'a
+    where
+        R: Reducer<T>,
+    {
+        data.windows(self.size)
+            .step_by(self.stride)
+            .map(|w| self.reducer.reduce(w))
+    }
+
+    pub fn apply<T>(&self, data: &[T]) -> Vec<R::Output>
+    where
+        R: Reducer<T>,
+    {
+        self.iter(data).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DATA: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    fn sums<T: Num>(w: Windowed<Sum>, data: &[T]) -> Result<Vec<T>> {
+        w.iter(data).collect()
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_eq!(
+            sums(Windowed::new(3, Sum), &DATA).unwrap(),
+            vec![607, 618, 618, 617, 647, 716, 769, 792]
+        );
+        assert_eq!(
+            sums(Windowed::new(3, Sum).with_stride(3), &DATA).unwrap(),
+            vec![607, 617, 769]
+        );
+        assert_eq!(sums(Windowed::new(1, Sum), &DATA).unwrap(), DATA.to_vec());
+        assert!(sums(Windowed::new(11, Sum), &DATA).unwrap().is_empty());
+
+        assert_eq!(
+            sums(Windowed::new(2, Sum), &[i32::MAX as i64, 1]).unwrap(),
+            vec![i32::MAX as i64 + 1]
+        );
+        match sums(Windowed::new(2, Sum), &[u64::MAX, 1]).unwrap_err() {
+            Error::Overflow(e) => assert_eq!(e, "sum of 2 readings doesn't fit in u64"),
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert!(sums(Windowed::new(2, Sum), &[f64::MAX, f64::MAX]).is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let data = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(Windowed::new(8, Mean).apply(&data), vec![5.0]);
+        assert_eq!(Windowed::new(8, StdDev).apply(&data), vec![2.0]);
+        assert_eq!(Windowed::new(8, Median).apply(&data), vec![4.5]);
+        assert_eq!(
+            Windowed::new(4, Median).with_stride(4).apply(&data),
+            vec![4.0, 6.0]
+        );
+
+        assert_eq!(
+            Windowed::new(4, Min).with_stride(2).apply(&DATA),
+            vec![199, 200, 200, 240]
+        );
+        assert_eq!(Windowed::new(4, Max).apply(&[3u64, 1, 4, 1, 5]), vec![4, 5]);
+        assert_eq!(Windowed::new(2, Mean).apply(&[1i128, 2]), vec![1.5]);
+    }
+}